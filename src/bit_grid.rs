@@ -0,0 +1,147 @@
+use std::fmt::{Display, Formatter};
+use std::str::FromStr;
+
+use anyhow::bail;
+
+const WORD_BITS: usize = 64;
+
+#[derive(Debug, Clone, Eq, PartialEq)]
+pub(crate) struct BitGrid {
+    width: usize,
+    height: usize,
+    words_per_row: usize,
+    words: Vec<u64>,
+}
+
+impl BitGrid {
+    pub fn new(width: usize, height: usize) -> Self {
+        let words_per_row = width.div_ceil(WORD_BITS);
+        Self {
+            width,
+            height,
+            words_per_row,
+            words: vec![0; words_per_row * height],
+        }
+    }
+
+    pub fn width(&self) -> usize {
+        self.width
+    }
+
+    pub fn height(&self) -> usize {
+        self.height
+    }
+
+    fn index(&self, x: usize, y: usize) -> (usize, u64) {
+        assert!(
+            x < self.width && y < self.height,
+            "({}, {}) is outside of {}x{} grid",
+            x,
+            y,
+            self.width,
+            self.height
+        );
+        (y * self.words_per_row + x / WORD_BITS, 1 << (x % WORD_BITS))
+    }
+
+    pub fn get(&self, x: usize, y: usize) -> bool {
+        let (word, mask) = self.index(x, y);
+        self.words[word] & mask != 0
+    }
+
+    pub fn set(&mut self, x: usize, y: usize, value: bool) {
+        let (word, mask) = self.index(x, y);
+        if value {
+            self.words[word] |= mask;
+        } else {
+            self.words[word] &= !mask;
+        }
+    }
+
+    pub fn set_row(&mut self, y: usize, bytes: &[u8]) {
+        for x in 0..self.width.min(bytes.len() * 8) {
+            self.set(x, y, bytes[x / 8] & (0x80 >> (x % 8)) != 0);
+        }
+    }
+
+    pub fn count_ones(&self) -> usize {
+        self.words
+            .iter()
+            .map(|word| word.count_ones() as usize)
+            .sum()
+    }
+}
+
+impl Display for BitGrid {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        for y in 0..self.height {
+            if y > 0 {
+                writeln!(f)?;
+            }
+            for x in 0..self.width {
+                write!(f, "{}", if self.get(x, y) { '#' } else { '.' })?;
+            }
+        }
+        Ok(())
+    }
+}
+
+impl FromStr for BitGrid {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let lines: Vec<&str> = s.lines().collect();
+        let width = lines.first().map(|line| line.len()).unwrap_or(0);
+        let mut grid = BitGrid::new(width, lines.len());
+        for (y, line) in lines.iter().enumerate() {
+            if line.len() != width {
+                bail!(
+                    "line {} has length {}, expected {}",
+                    y + 1,
+                    line.len(),
+                    width
+                );
+            }
+            for (x, c) in line.chars().enumerate() {
+                match c {
+                    '#' => grid.set(x, y, true),
+                    '.' => {}
+                    other => bail!("invalid square '{}'", other),
+                }
+            }
+        }
+        Ok(grid)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_bits() {
+        let mut grid = BitGrid::new(70, 3);
+        assert_eq!(0, grid.count_ones());
+        grid.set(0, 0, true);
+        grid.set(69, 2, true);
+        grid.set(64, 1, true);
+        grid.set(64, 1, false);
+        assert!(grid.get(0, 0) && grid.get(69, 2));
+        assert!(!grid.get(64, 1) && !grid.get(1, 0));
+        assert_eq!(2, grid.count_ones());
+
+        let mut grid = BitGrid::new(10, 2);
+        grid.set_row(1, &[0b1010_0000, 0b1100_0000, 0xff]);
+        assert_eq!("..........\n#.#.....##", grid.to_string());
+        assert_eq!(4, grid.count_ones());
+        assert_eq!(grid, grid.to_string().parse().unwrap());
+        assert!("##\n#".parse::<BitGrid>().is_err());
+        assert!("#o".parse::<BitGrid>().is_err());
+    }
+
+    #[test]
+    #[should_panic]
+    fn test_out_of_bounds() {
+        BitGrid::new(8, 8).get(8, 0);
+    }
+}
@@ -1,97 +1,206 @@
+use anyhow::bail;
+
+pub(crate) use cli::run_knothash;
+pub use fixed::knot_hash_bytes;
+
+mod analysis;
+mod cli;
+mod fixed;
+mod streaming;
+
 pub(crate) fn run() {
+    let args: Vec<String> = std::env::args().skip(2).collect();
+    match args.first().map(|arg| arg.as_str()) {
+        Some("analyze") => return analysis::run_analysis(&args[1..]),
+        Some("variant") => return run_variant(&args[1..]),
+        _ => {}
+    }
     let input = std::fs::read_to_string("input/input10.txt").unwrap();
-    let mut knot = get_knot(256);
-    execute_round(&mut knot, &parse_input(&input), &mut 0, &mut 0);
-    println!("multiplying first 2 numbers: {}", knot[0] * knot[1]);
+    let knot = KnotHasher::builder()
+        .rounds(1)
+        .build()
+        .unwrap()
+        .sparse_hash(&parse_input(&input));
+    println!(
+        "multiplying first 2 numbers: {}",
+        knot[0] as usize * knot[1] as usize
+    );
 
     println!("hash: {}", knot_hash(&input));
 }
 
-fn parse_input(input: &str) -> Vec<usize> {
-    input
-        .split(',')
-        .map(|i| i.trim().parse::<usize>().unwrap())
-        .collect()
+#[derive(Debug, Clone, Eq, PartialEq)]
+pub struct KnotHasher {
+    size: usize,
+    rounds: usize,
+    suffix: Vec<u8>,
+    block_size: usize,
+}
+
+impl Default for KnotHasher {
+    fn default() -> Self {
+        Self {
+            size: 256,
+            rounds: 64,
+            suffix: vec![17, 31, 73, 47, 23],
+            block_size: 16,
+        }
+    }
 }
 
-fn knot_hash_internal(input: &str, bin: bool) -> String {
-    let input = convert_to_ascii_codes(&input);
-    let mut knot = get_knot(256);
-    let mut current_position = 0;
-    let mut skip_size = 0;
-    for _ in 0..64 {
-        execute_round(&mut knot, &input, &mut current_position, &mut skip_size);
+impl KnotHasher {
+    pub fn builder() -> KnotHasherBuilder {
+        KnotHasherBuilder(Self::default())
+    }
+    pub fn sparse_hash(&self, lengths: &[usize]) -> Vec<u8> {
+        let mut knot = get_knot(self.size);
+        let mut current_position = 0;
+        let mut skip_size = 0;
+        for _ in 0..self.rounds {
+            execute_round(&mut knot, lengths, &mut current_position, &mut skip_size);
+        }
+        knot
     }
-    let dense_hash = calculate_dense_hash(&knot, 16);
-    if bin {
-        dense_hash_to_bin_string(&dense_hash)
-    } else {
-        dense_hash_to_string(&dense_hash)
+    pub fn hash(&self, input: &[u8]) -> Vec<u8> {
+        let lengths: Vec<usize> = input
+            .iter()
+            .chain(self.suffix.iter())
+            .map(|&byte| byte as usize)
+            .collect();
+        calculate_dense_hash(&self.sparse_hash(&lengths), self.block_size)
     }
 }
 
-pub fn knot_hash(input: &str) -> String {
-    knot_hash_internal(input, false)
+#[derive(Debug, Clone)]
+pub struct KnotHasherBuilder(KnotHasher);
+
+impl KnotHasherBuilder {
+    pub fn size(mut self, size: usize) -> Self {
+        self.0.size = size;
+        self
+    }
+    pub fn rounds(mut self, rounds: usize) -> Self {
+        self.0.rounds = rounds;
+        self
+    }
+    pub fn suffix(mut self, suffix: &[u8]) -> Self {
+        self.0.suffix = suffix.to_vec();
+        self
+    }
+    pub fn block_size(mut self, block_size: usize) -> Self {
+        self.0.block_size = block_size;
+        self
+    }
+    pub fn build(self) -> anyhow::Result<KnotHasher> {
+        let hasher = self.0;
+        if !(1..=256).contains(&hasher.size) {
+            bail!("knot size must be between 1 and 256, got {}", hasher.size);
+        }
+        if hasher.block_size == 0 || !hasher.size.is_multiple_of(hasher.block_size) {
+            bail!(
+                "block size {} does not divide the knot size {}",
+                hasher.block_size,
+                hasher.size
+            );
+        }
+        Ok(hasher)
+    }
+}
+
+fn run_variant(args: &[String]) {
+    const USAGE: &str =
+        "usage: variant [--size N] [--rounds N] [--suffix N,N,...] [--block-size N] <input>";
+    let number = |value: Option<&String>, name: &str| -> usize {
+        value
+            .expect(USAGE)
+            .parse()
+            .unwrap_or_else(|_| panic!("{} should be an integer", name))
+    };
+    let mut builder = KnotHasher::builder();
+    let mut input = None;
+    let mut args = args.iter();
+    while let Some(arg) = args.next() {
+        builder = match arg.as_str() {
+            "--size" => builder.size(number(args.next(), "size")),
+            "--rounds" => builder.rounds(number(args.next(), "rounds")),
+            "--block-size" => builder.block_size(number(args.next(), "block size")),
+            "--suffix" => {
+                let suffix = args.next().expect(USAGE);
+                let suffix: Vec<u8> = if suffix.is_empty() {
+                    vec![]
+                } else {
+                    suffix
+                        .split(',')
+                        .map(|byte| byte.trim().parse().expect("suffix should be bytes"))
+                        .collect()
+                };
+                builder.suffix(&suffix)
+            }
+            flag if flag.starts_with('-') => panic!("unknown option '{}'\n{}", flag, USAGE),
+            _ => {
+                input = Some(arg.clone());
+                builder
+            }
+        };
+    }
+    let hasher = builder.build().unwrap();
+    let input = input.expect(USAGE);
+    println!("{}", dense_hash_to_string(&hasher.hash(input.as_bytes())));
 }
 
-pub fn knot_hash_bin(input: &str) -> String {
-    knot_hash_internal(input, true)
+fn parse_input(input: &str) -> Vec<usize> {
+    input
+        .split(',')
+        .map(|i| i.trim().parse::<usize>().unwrap())
+        .collect()
 }
 
-fn get_knot(size: usize) -> Vec<usize> {
-    (0..size).collect()
+pub fn knot_hash(input: &str) -> String {
+    dense_hash_to_string(&knot_hash_bytes(input.trim().as_bytes()))
+}
+
+fn get_knot(size: usize) -> Vec<u8> {
+    (0..size).map(|i| i as u8).collect()
 }
 
 fn execute_round(
-    knot: &mut Vec<usize>,
+    knot: &mut [u8],
     input: &[usize],
     current_position: &mut usize,
     skip_size: &mut usize,
 ) {
     for &len in input.iter() {
-        reverse_slice(knot, *current_position, len);
-        *current_position += len + *skip_size;
+        if len <= knot.len() {
+            reverse_slice(knot, *current_position, len);
+        }
+        *current_position = (*current_position + len + *skip_size) % knot.len();
         *skip_size += 1;
     }
 }
 
-fn reverse_slice(knot: &mut Vec<usize>, start: usize, len: usize) {
+fn reverse_slice(knot: &mut [u8], start: usize, len: usize) {
+    if len == 0 {
+        return;
+    }
     let end = start + len - 1;
     for i in 0..len / 2 {
         let left = (start + i) % knot.len();
         let right = (end - i) % knot.len();
-        let tmp = knot[left];
-        knot[left] = knot[right];
-        knot[right] = tmp;
+        knot.swap(left, right);
     }
 }
 
-fn calculate_dense_hash(knot: &[usize], len: usize) -> Vec<usize> {
-    (0..knot.len() / len)
-        .map(|i| {
-            knot[i * len..(i + 1) * len]
-                .iter()
-                .copied()
-                .reduce(|accum, next| accum ^ next)
-                .unwrap()
-        })
-        .collect()
-}
-
-fn convert_to_ascii_codes(input: &str) -> Vec<usize> {
-    input
-        .trim()
-        .chars()
-        .map(|c| c as u8 as usize)
-        .chain(vec![17usize, 31, 73, 47, 23])
+fn calculate_dense_hash(knot: &[u8], len: usize) -> Vec<u8> {
+    knot.chunks(len)
+        .map(|block| block.iter().fold(0, |accum, next| accum ^ next))
         .collect()
 }
 
-fn dense_hash_to_string(hash: &[usize]) -> String {
+fn dense_hash_to_string(hash: &[u8]) -> String {
     hash.iter().map(|&val| format!("{:02x}", val)).collect()
 }
 
-fn dense_hash_to_bin_string(hash: &[usize]) -> String {
+fn dense_hash_to_bin_string(hash: &[u8]) -> String {
     hash.iter().map(|&val| format!("{:08b}", val)).collect()
 }
 
@@ -123,4 +232,42 @@ mod tests {
     fn test5() {
         assert_eq!(knot_hash("1,2,4"), "63960835bcdc130f0b66d7ff4f6a5a8e");
     }
+    #[test]
+    fn test_knot_hasher() {
+        assert_eq!(
+            vec![3, 4, 2, 1, 0],
+            KnotHasher::builder()
+                .size(5)
+                .rounds(1)
+                .block_size(5)
+                .build()
+                .unwrap()
+                .sparse_hash(&[3, 4, 1, 5])
+        );
+        let hash = KnotHasher::default().hash(b"AoC 2017");
+        assert_eq!(
+            "33efeb34ea91902bb2f59c9920caa6cd",
+            dense_hash_to_string(&hash)
+        );
+
+        let hasher = KnotHasher::builder()
+            .rounds(16)
+            .suffix(&[])
+            .block_size(8)
+            .build()
+            .unwrap();
+        assert_eq!(32, hasher.hash(b"AoC 2017").len());
+        assert_ne!(hasher.hash(b"AoC 2017"), hasher.hash(b"AoC 2018"));
+        let hasher = KnotHasher::builder().size(16).build().unwrap();
+        assert_eq!(1, hasher.hash(b"").len());
+    }
+
+    #[test]
+    fn test_invalid_knot_hasher() {
+        assert!(KnotHasher::builder().size(0).build().is_err());
+        assert!(KnotHasher::builder().size(257).build().is_err());
+        assert!(KnotHasher::builder().block_size(0).build().is_err());
+        assert!(KnotHasher::builder().size(20).build().is_err());
+        assert!(KnotHasher::builder().size(20).block_size(4).build().is_ok());
+    }
 }
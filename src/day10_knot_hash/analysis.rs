@@ -0,0 +1,261 @@
+use std::collections::HashMap;
+
+use anyhow::bail;
+use rayon::prelude::*;
+
+use super::knot_hash_bytes;
+
+type HashFunction = fn(&[u8]) -> [u8; 16];
+
+// the search keeps every prefix it has seen, around 2^(bits / 2) of them
+const MAX_PREFIX_BITS: u32 = 40;
+
+const HASHES: [(&str, HashFunction); 2] = [("knot hash", knot_hash_bytes), ("md5", md5_bytes)];
+
+fn md5_bytes(data: &[u8]) -> [u8; 16] {
+    md5::compute(data).0
+}
+
+pub(super) fn run_analysis(args: &[String]) {
+    let samples = args
+        .first()
+        .map(|arg| arg.parse().expect("samples should be an integer"))
+        .unwrap_or(1_000);
+    let prefix_bits = args
+        .get(1)
+        .map(|arg| arg.parse().expect("prefix bits should be an integer"))
+        .unwrap_or(32);
+    let input_len = 16;
+    for (name, hash) in HASHES {
+        println!("{}:", name);
+        let avalanche = avalanche(hash, samples, input_len);
+        println!(
+            "  avalanche: {:.2} of 128 output bits flip per input bit flip (min {}, max {}), worst output bit flips with p = {:.3}",
+            avalanche.mean, avalanche.min, avalanche.max, avalanche.worst_flip_probability
+        );
+        let bias = bias(hash, samples * 100, input_len);
+        println!(
+            "  bias: worst output bit is set with p = {:.4} (bit {})",
+            bias.worst_probability, bias.worst_bit
+        );
+        match prefix_collision(hash, prefix_bits, u64::MAX).unwrap() {
+            Some(collision) => println!(
+                "  {} bit prefix collision after {} hashes: {:?} and {:?}",
+                prefix_bits,
+                collision.attempts,
+                String::from_utf8_lossy(&collision.first),
+                String::from_utf8_lossy(&collision.second)
+            ),
+            None => println!("  no {} bit prefix collision found", prefix_bits),
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+pub(super) struct Avalanche {
+    pub mean: f64,
+    pub min: u32,
+    pub max: u32,
+    pub worst_flip_probability: f64,
+}
+
+#[derive(Debug, Clone)]
+struct FlipCounts {
+    trials: u64,
+    total: u64,
+    min: u32,
+    max: u32,
+    per_bit: Vec<u64>,
+}
+
+impl FlipCounts {
+    pub fn new() -> Self {
+        Self {
+            trials: 0,
+            total: 0,
+            min: u32::MAX,
+            max: 0,
+            per_bit: vec![0; 128],
+        }
+    }
+    pub fn add(mut self, a: &[u8; 16], b: &[u8; 16]) -> Self {
+        let difference = u128::from_be_bytes(*a) ^ u128::from_be_bytes(*b);
+        let flipped = difference.count_ones();
+        self.trials += 1;
+        self.total += flipped as u64;
+        self.min = self.min.min(flipped);
+        self.max = self.max.max(flipped);
+        for (bit, count) in self.per_bit.iter_mut().enumerate() {
+            *count += ((difference >> (127 - bit)) & 1) as u64;
+        }
+        self
+    }
+    pub fn merge(mut self, other: Self) -> Self {
+        self.trials += other.trials;
+        self.total += other.total;
+        self.min = self.min.min(other.min);
+        self.max = self.max.max(other.max);
+        for (count, other) in self.per_bit.iter_mut().zip(other.per_bit) {
+            *count += other;
+        }
+        self
+    }
+}
+
+pub(super) fn avalanche(hash: HashFunction, samples: usize, input_len: usize) -> Avalanche {
+    let counts = (0..samples as u64)
+        .into_par_iter()
+        .fold(FlipCounts::new, |counts, sample| {
+            let input = random_input(sample, input_len);
+            let original = hash(&input);
+            (0..input_len * 8).fold(counts, |counts, bit| {
+                let mut flipped = input.clone();
+                flipped[bit / 8] ^= 1 << (bit % 8);
+                counts.add(&original, &hash(&flipped))
+            })
+        })
+        .reduce(FlipCounts::new, FlipCounts::merge);
+    let trials = counts.trials.max(1) as f64;
+    Avalanche {
+        mean: counts.total as f64 / trials,
+        min: counts.min,
+        max: counts.max,
+        worst_flip_probability: counts
+            .per_bit
+            .iter()
+            .map(|&count| count as f64 / trials)
+            .max_by(|a, b| (a - 0.5).abs().total_cmp(&(b - 0.5).abs()))
+            .unwrap_or_default(),
+    }
+}
+
+#[derive(Debug, Clone)]
+pub(super) struct Bias {
+    pub worst_bit: usize,
+    pub worst_probability: f64,
+}
+
+pub(super) fn bias(hash: HashFunction, samples: usize, input_len: usize) -> Bias {
+    let ones = (0..samples as u64)
+        .into_par_iter()
+        .fold(
+            || vec![0u64; 128],
+            |mut ones, sample| {
+                let value = u128::from_be_bytes(hash(&random_input(sample, input_len)));
+                for (bit, count) in ones.iter_mut().enumerate() {
+                    *count += ((value >> (127 - bit)) & 1) as u64;
+                }
+                ones
+            },
+        )
+        .reduce(
+            || vec![0u64; 128],
+            |a, b| a.iter().zip(b.iter()).map(|(a, b)| a + b).collect(),
+        );
+    let (worst_bit, worst_probability) = ones
+        .iter()
+        .map(|&count| count as f64 / samples.max(1) as f64)
+        .enumerate()
+        .max_by(|(_, a), (_, b)| (a - 0.5).abs().total_cmp(&(b - 0.5).abs()))
+        .unwrap();
+    Bias {
+        worst_bit,
+        worst_probability,
+    }
+}
+
+#[derive(Debug, Clone)]
+pub(super) struct Collision {
+    pub first: Vec<u8>,
+    pub second: Vec<u8>,
+    pub attempts: u64,
+}
+
+pub(super) fn prefix_collision(
+    hash: HashFunction,
+    bits: u32,
+    max_attempts: u64,
+) -> anyhow::Result<Option<Collision>> {
+    if !(1..=MAX_PREFIX_BITS).contains(&bits) {
+        bail!("prefix must be 1 to {} bits, got {}", MAX_PREFIX_BITS, bits);
+    }
+    // a collision is all but certain long before 8 times the birthday bound
+    let max_attempts = max_attempts.min(1 << (bits / 2 + 3));
+    const BATCH: u64 = 1 << 14;
+    let prefix = |i: u64| (u128::from_be_bytes(hash(&counter_input(i))) >> (128 - bits)) as u64;
+    let mut seen = HashMap::new();
+    let mut start = 0;
+    while start < max_attempts {
+        let end = (start + BATCH).min(max_attempts);
+        let prefixes: Vec<(u64, u64)> = (start..end)
+            .into_par_iter()
+            .map(|i| (i, prefix(i)))
+            .collect();
+        for (i, prefix) in prefixes {
+            if let Some(&j) = seen.get(&prefix) {
+                return Ok(Some(Collision {
+                    first: counter_input(j),
+                    second: counter_input(i),
+                    attempts: i + 1,
+                }));
+            }
+            seen.insert(prefix, i);
+        }
+        start = end;
+    }
+    Ok(None)
+}
+
+fn counter_input(i: u64) -> Vec<u8> {
+    i.to_string().into_bytes()
+}
+
+fn random_input(seed: u64, len: usize) -> Vec<u8> {
+    let mut state = seed.wrapping_add(0x9e3779b97f4a7c15);
+    (0..len)
+        .map(|_| {
+            state ^= state << 13;
+            state ^= state >> 7;
+            state ^= state << 17;
+            (state >> 32) as u8
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_avalanche() {
+        for (name, hash) in HASHES {
+            let avalanche = avalanche(hash, 20, 8);
+            assert!((62.0..66.0).contains(&avalanche.mean), "{}", name);
+            assert!(avalanche.min >= 40 && avalanche.max <= 88, "{}", name);
+            assert!(
+                (avalanche.worst_flip_probability - 0.5).abs() < 0.1,
+                "{}",
+                name
+            );
+        }
+    }
+
+    #[test]
+    fn test_bias() {
+        let md5 = bias(md5_bytes, 4_000, 8);
+        assert!((md5.worst_probability - 0.5).abs() < 0.05);
+        assert!(md5.worst_bit < 128);
+    }
+
+    #[test]
+    fn test_prefix_collision() {
+        for (_, hash) in HASHES {
+            let collision = prefix_collision(hash, 16, 1 << 20).unwrap().unwrap();
+            assert_ne!(collision.first, collision.second);
+            assert_eq!(hash(&collision.first)[..2], hash(&collision.second)[..2]);
+        }
+        assert!(prefix_collision(md5_bytes, 40, 100).unwrap().is_none());
+        assert!(prefix_collision(md5_bytes, 41, 100).is_err());
+        assert!(prefix_collision(md5_bytes, 0, 100).is_err());
+    }
+}
@@ -0,0 +1,269 @@
+use std::io::Read;
+use std::str::FromStr;
+
+use anyhow::{bail, Context};
+
+use super::streaming::KnotHash;
+use super::{dense_hash_to_bin_string, dense_hash_to_string};
+
+const USAGE: &str = "usage: knothash [--format hex|binary|base64] [--trim] [--check] [FILE]...
+With no FILE, or when FILE is -, read standard input.";
+
+#[derive(Debug, Copy, Clone, Eq, PartialEq)]
+enum Format {
+    Hex,
+    Binary,
+    Base64,
+}
+
+impl Format {
+    pub fn encode(&self, hash: &[u8]) -> String {
+        match self {
+            Format::Hex => dense_hash_to_string(hash),
+            Format::Binary => dense_hash_to_bin_string(hash),
+            Format::Base64 => base64::encode(hash),
+        }
+    }
+}
+
+impl FromStr for Format {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        Ok(match s {
+            "hex" => Format::Hex,
+            "binary" => Format::Binary,
+            "base64" => Format::Base64,
+            other => bail!("unknown format '{}'", other),
+        })
+    }
+}
+
+#[derive(Debug)]
+struct Options {
+    format: Format,
+    trim: bool,
+    check: bool,
+    files: Vec<String>,
+}
+
+impl Options {
+    pub fn parse(args: &[String]) -> anyhow::Result<Self> {
+        let mut options = Options {
+            format: Format::Hex,
+            trim: false,
+            check: false,
+            files: vec![],
+        };
+        let mut args = args.iter();
+        while let Some(arg) = args.next() {
+            match arg.as_str() {
+                "--format" | "-f" => {
+                    options.format = args.next().context("--format needs a value")?.parse()?
+                }
+                "--trim" => options.trim = true,
+                "--check" | "-c" => options.check = true,
+                "--help" | "-h" => bail!("{}", USAGE),
+                "-" => options.files.push(arg.clone()),
+                flag if flag.starts_with('-') => bail!("unknown option '{}'\n{}", flag, USAGE),
+                _ => options.files.push(arg.clone()),
+            }
+        }
+        if options.files.is_empty() {
+            options.files.push("-".to_string());
+        }
+        Ok(options)
+    }
+}
+
+pub(crate) fn run_knothash(args: &[String]) -> i32 {
+    let options = match Options::parse(args) {
+        Ok(options) => options,
+        Err(e) => {
+            eprintln!("knothash: {}", e);
+            return 2;
+        }
+    };
+    let mut failed = false;
+    for file in options.files.iter() {
+        let data = match read(file) {
+            Ok(data) => data,
+            Err(e) => {
+                eprintln!("knothash: {}: {:#}", file, e);
+                failed = true;
+                continue;
+            }
+        };
+        if options.check {
+            failed |= !check(&String::from_utf8_lossy(&data), &options, read).is_ok();
+        } else {
+            println!(
+                "{}  {}",
+                options.format.encode(&hash(&data, options.trim)),
+                file
+            );
+        }
+    }
+    if failed {
+        1
+    } else {
+        0
+    }
+}
+
+fn read(file: &str) -> anyhow::Result<Vec<u8>> {
+    let mut data = Vec::new();
+    if file == "-" {
+        std::io::stdin().read_to_end(&mut data)?;
+    } else {
+        std::fs::File::open(file)?.read_to_end(&mut data)?;
+    }
+    Ok(data)
+}
+
+fn hash(data: &[u8], trim: bool) -> [u8; 16] {
+    let data = if trim {
+        let start = data
+            .iter()
+            .position(|b| !b.is_ascii_whitespace())
+            .unwrap_or(data.len());
+        let end = data
+            .iter()
+            .rposition(|b| !b.is_ascii_whitespace())
+            .map(|i| i + 1)
+            .unwrap_or(start);
+        &data[start..end]
+    } else {
+        data
+    };
+    let mut hash = KnotHash::new();
+    hash.update(data);
+    hash.finalize()
+}
+
+#[derive(Debug, Default, Eq, PartialEq)]
+struct CheckReport {
+    ok: usize,
+    mismatched: usize,
+    unreadable: usize,
+    malformed: usize,
+}
+
+impl CheckReport {
+    pub fn is_ok(&self) -> bool {
+        self.mismatched == 0 && self.unreadable == 0 && self.malformed == 0
+    }
+}
+
+fn check(
+    list: &str,
+    options: &Options,
+    read: impl Fn(&str) -> anyhow::Result<Vec<u8>>,
+) -> CheckReport {
+    let mut report = CheckReport::default();
+    for line in list.lines().filter(|line| !line.trim().is_empty()) {
+        let (expected, file) = match line.split_once("  ") {
+            Some(entry) => entry,
+            None => {
+                report.malformed += 1;
+                continue;
+            }
+        };
+        match read(file) {
+            Ok(data) if options.format.encode(&hash(&data, options.trim)) == expected => {
+                println!("{}: OK", file);
+                report.ok += 1;
+            }
+            Ok(_) => {
+                println!("{}: FAILED", file);
+                report.mismatched += 1;
+            }
+            Err(e) => {
+                println!("{}: FAILED open or read", file);
+                eprintln!("knothash: {}: {:#}", file, e);
+                report.unreadable += 1;
+            }
+        }
+    }
+    if report.malformed > 0 {
+        eprintln!(
+            "knothash: WARNING: {} line(s) are improperly formatted",
+            report.malformed
+        );
+    }
+    if report.unreadable > 0 {
+        eprintln!(
+            "knothash: WARNING: {} listed file(s) could not be read",
+            report.unreadable
+        );
+    }
+    if report.mismatched > 0 {
+        eprintln!(
+            "knothash: WARNING: {} computed checksum(s) did NOT match",
+            report.mismatched
+        );
+    }
+    report
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn args(s: &str) -> Vec<String> {
+        s.split_whitespace().map(|arg| arg.to_string()).collect()
+    }
+
+    #[test]
+    fn test_formats() {
+        let hash = hash(b"  AoC 2017\n", true);
+        assert_eq!(
+            "33efeb34ea91902bb2f59c9920caa6cd",
+            Format::Hex.encode(&hash)
+        );
+        assert_eq!(128, Format::Binary.encode(&hash).len());
+        assert!(Format::Binary.encode(&hash).starts_with("00110011"));
+        assert_eq!("M+/rNOqRkCuy9ZyZIMqmzQ==", Format::Base64.encode(&hash));
+        assert!("octal".parse::<Format>().is_err());
+    }
+
+    #[test]
+    fn test_options() {
+        let options = Options::parse(&args("--format base64 --trim a.txt -")).unwrap();
+        assert_eq!(Format::Base64, options.format);
+        assert!(options.trim);
+        assert!(!options.check);
+        assert_eq!(args("a.txt -"), options.files);
+        assert_eq!(args("-"), Options::parse(&[]).unwrap().files);
+        assert!(Options::parse(&args("--format")).is_err());
+        assert!(Options::parse(&args("--bogus")).is_err());
+    }
+
+    #[test]
+    fn test_check() {
+        let options = Options::parse(&args("--check --trim")).unwrap();
+        let read = |file: &str| -> anyhow::Result<Vec<u8>> {
+            match file {
+                "a.txt" => Ok(b"AoC 2017\n".to_vec()),
+                "b.txt" => Ok(b"1,2,3".to_vec()),
+                _ => bail!("not found"),
+            }
+        };
+        let list = "33efeb34ea91902bb2f59c9920caa6cd  a.txt
+3efbe78a8d82f29979031a4aa0b16a9d  b.txt
+63960835bcdc130f0b66d7ff4f6a5a8e  b.txt
+a2582a3a0e66e6e86e3812dcb672a272  missing.txt
+not a checksum line
+";
+        assert_eq!(
+            CheckReport {
+                ok: 2,
+                mismatched: 1,
+                unreadable: 1,
+                malformed: 1
+            },
+            check(list, &options, read)
+        );
+        assert!(check(&list[..list.find("6396").unwrap()], &options, read).is_ok());
+    }
+}
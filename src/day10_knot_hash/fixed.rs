@@ -0,0 +1,90 @@
+const SUFFIX: [u8; 5] = [17, 31, 73, 47, 23];
+
+pub fn knot_hash_bytes(input: &[u8]) -> [u8; 16] {
+    let mut knot = [0u8; 256];
+    for (i, value) in knot.iter_mut().enumerate() {
+        *value = i as u8;
+    }
+    // the knot is kept rotated so the current position is always at index 0
+    let mut rotation = 0;
+    let mut skip_size = 0;
+    for _ in 0..64 {
+        for &len in input.iter().chain(SUFFIX.iter()) {
+            let len = len as usize;
+            knot[..len].reverse();
+            let step = (len + skip_size) % 256;
+            knot.rotate_left(step);
+            rotation = (rotation + step) % 256;
+            skip_size += 1;
+        }
+    }
+    knot.rotate_right(rotation);
+
+    let mut dense_hash = [0u8; 16];
+    for (value, block) in dense_hash.iter_mut().zip(knot.chunks_exact(16)) {
+        *value = block.iter().fold(0, |accum, next| accum ^ next);
+    }
+    dense_hash
+}
+
+#[cfg(test)]
+mod tests {
+    use utils::timer::Timer;
+
+    use super::super::{dense_hash_to_string, KnotHasher};
+    use super::*;
+
+    #[test]
+    fn test_vectors() {
+        for (input, expected) in [
+            ("", "a2582a3a0e66e6e86e3812dcb672a272"),
+            ("AoC 2017", "33efeb34ea91902bb2f59c9920caa6cd"),
+            ("1,2,3", "3efbe78a8d82f29979031a4aa0b16a9d"),
+            ("1,2,4", "63960835bcdc130f0b66d7ff4f6a5a8e"),
+        ] {
+            assert_eq!(
+                expected,
+                dense_hash_to_string(&knot_hash_bytes(input.as_bytes()))
+            );
+        }
+    }
+
+    #[test]
+    fn test_same_as_knot_hasher() {
+        let hasher = KnotHasher::default();
+        let mut input = vec![];
+        for i in 0..300u32 {
+            input.push((i * 7919 % 256) as u8);
+            assert_eq!(hasher.hash(&input), knot_hash_bytes(&input));
+        }
+    }
+
+    #[test]
+    #[ignore]
+    fn benchmark() {
+        let inputs: Vec<String> = (0..128 * 20)
+            .map(|row| format!("flqrgnkx-{}", row))
+            .collect();
+        let expected: Vec<Vec<u8>> = {
+            let _timer =
+                Timer::start(|elapsed| println!("knot hasher took {} ms.", elapsed.as_millis()));
+            let hasher = KnotHasher::default();
+            inputs
+                .iter()
+                .map(|input| hasher.hash(input.as_bytes()))
+                .collect()
+        };
+        let actual: Vec<[u8; 16]> = {
+            let _timer = Timer::start(|elapsed| {
+                println!("fixed knot hash took {} ms.", elapsed.as_millis())
+            });
+            inputs
+                .iter()
+                .map(|input| knot_hash_bytes(input.as_bytes()))
+                .collect()
+        };
+        for (expected, actual) in expected.iter().zip(actual.iter()) {
+            assert_eq!(expected.as_slice(), actual.as_slice());
+        }
+    }
+}
@@ -0,0 +1,127 @@
+use digest::consts::U16;
+use digest::{FixedOutput, FixedOutputReset, HashMarker, Output, OutputSizeUser, Reset, Update};
+
+use super::knot_hash_bytes;
+
+// every round replays the whole input, so bytes are buffered until the hash is finalized
+#[derive(Debug, Clone, Default)]
+pub struct KnotHash {
+    buffer: Vec<u8>,
+}
+
+impl KnotHash {
+    pub fn new() -> Self {
+        Self::default()
+    }
+    pub fn update(&mut self, data: impl AsRef<[u8]>) {
+        self.buffer.extend_from_slice(data.as_ref());
+    }
+    pub fn finalize(self) -> [u8; 16] {
+        self.digest()
+    }
+    fn digest(&self) -> [u8; 16] {
+        knot_hash_bytes(&self.buffer)
+    }
+}
+
+impl HashMarker for KnotHash {}
+
+impl OutputSizeUser for KnotHash {
+    type OutputSize = U16;
+}
+
+impl Update for KnotHash {
+    fn update(&mut self, data: &[u8]) {
+        KnotHash::update(self, data);
+    }
+}
+
+impl FixedOutput for KnotHash {
+    fn finalize_into(self, out: &mut Output<Self>) {
+        out.copy_from_slice(&self.digest());
+    }
+}
+
+impl Reset for KnotHash {
+    fn reset(&mut self) {
+        self.buffer.clear();
+    }
+}
+
+impl FixedOutputReset for KnotHash {
+    fn finalize_into_reset(&mut self, out: &mut Output<Self>) {
+        out.copy_from_slice(&self.digest());
+        self.buffer.clear();
+    }
+}
+
+impl std::hash::Hasher for KnotHash {
+    fn finish(&self) -> u64 {
+        u64::from_le_bytes(self.digest()[..8].try_into().unwrap())
+    }
+    fn write(&mut self, bytes: &[u8]) {
+        self.update(bytes);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::hash::{Hash, Hasher};
+
+    use digest::Digest;
+
+    use super::super::{dense_hash_to_string, knot_hash};
+    use super::*;
+
+    #[test]
+    fn test_streaming() {
+        let input = "AoC 2017";
+        let mut hash = KnotHash::new();
+        hash.update("AoC");
+        hash.update(b" 20");
+        hash.update(vec![b'1', b'7']);
+        assert_eq!(knot_hash(input), dense_hash_to_string(&hash.finalize()));
+
+        let mut hash = KnotHash::new();
+        hash.update([]);
+        assert_eq!(
+            "a2582a3a0e66e6e86e3812dcb672a272",
+            dense_hash_to_string(&hash.finalize())
+        );
+    }
+
+    #[test]
+    fn test_digest() {
+        assert_eq!(
+            "3efbe78a8d82f29979031a4aa0b16a9d",
+            dense_hash_to_string(&<KnotHash as Digest>::digest(b"1,2,3"))
+        );
+        let mut hash = <KnotHash as Digest>::new();
+        Digest::update(&mut hash, b"1,2,");
+        Digest::update(&mut hash, b"4");
+        assert_eq!(
+            "63960835bcdc130f0b66d7ff4f6a5a8e",
+            dense_hash_to_string(&hash.finalize_reset())
+        );
+        assert_eq!(
+            "a2582a3a0e66e6e86e3812dcb672a272",
+            dense_hash_to_string(&hash.finalize())
+        );
+
+        fn digest_hex<D: Digest>(data: &[u8]) -> String {
+            dense_hash_to_string(&D::digest(data))
+        }
+        assert_eq!(knot_hash("1,2,4"), digest_hex::<KnotHash>(b"1,2,4"));
+    }
+
+    #[test]
+    fn test_hasher() {
+        let hash = |value: &str| {
+            let mut hasher = KnotHash::new();
+            value.hash(&mut hasher);
+            hasher.finish()
+        };
+        assert_eq!(hash("AoC 2017"), hash("AoC 2017"));
+        assert_ne!(hash("AoC 2017"), hash("AoC 2018"));
+    }
+}
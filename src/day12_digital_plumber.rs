@@ -1,6 +1,8 @@
 use std::collections::{HashMap, HashSet};
 use std::str::FromStr;
 
+use crate::union_find::UnionFind;
+
 pub(crate) fn run() {
     let input = std::fs::read_to_string("input/input12.txt").unwrap();
     let connections: Connections = input.parse().unwrap();
@@ -11,38 +13,24 @@ pub(crate) fn run() {
 struct Connections(HashMap<u32, Vec<u32>>);
 
 impl Connections {
-    pub fn get_group(&self, with_id: u32) -> HashSet<u32> {
-        let mut group = HashSet::new();
-        group.insert(with_id);
-        let mut next = vec![with_id];
-        while !next.is_empty() {
-            let mut new_neighbors = vec![];
-            for next in next {
-                for &neighbor in self.0.get(&next).unwrap() {
-                    if !group.contains(&neighbor) {
-                        group.insert(neighbor);
-                        new_neighbors.push(neighbor);
-                    }
-                }
+    fn groups(&self) -> UnionFind {
+        let len = self.0.keys().map(|&id| id as usize + 1).max().unwrap_or(0);
+        let mut groups = UnionFind::new(len);
+        for (&id, neighbors) in self.0.iter() {
+            for &neighbor in neighbors {
+                groups.union(id as usize, neighbor as usize);
             }
-            next = new_neighbors;
         }
-
-        group
+        groups
+    }
+    pub fn get_group(&self, with_id: u32) -> HashSet<u32> {
+        let mut groups = self.groups();
+        (0..groups.len() as u32)
+            .filter(|&id| groups.connected(id as usize, with_id as usize))
+            .collect()
     }
     pub fn get_number_of_groups(&self) -> usize {
-        let mut already_seen = HashSet::new();
-        let mut num_groups = 0;
-        for i in 0..self.0.len() as u32 {
-            if already_seen.contains(&i) {
-                continue;
-            }
-            let group = self.get_group(i);
-            already_seen.extend(group);
-            num_groups += 1;
-        }
-
-        num_groups
+        self.groups().sets()
     }
 }
 
@@ -1,165 +1,262 @@
-use std::collections::{HashMap, HashSet};
+use anyhow::bail;
+use rayon::prelude::*;
 
-use crate::day10_knot_hash::knot_hash_bin;
+use crate::bit_grid::BitGrid;
+use crate::day10_knot_hash::knot_hash_bytes;
+use crate::union_find::UnionFind;
 
-pub(crate) fn run() {
-    let input = std::fs::read_to_string("input/input14.txt").unwrap();
-    println!("used squares: {}", count_used_squares(&input));
-    println!("regions: {}", count_regions(&input));
-}
+use regions::RegionMap;
 
-fn count_used_squares(input: &str) -> usize {
-    (0..128)
-        .map(|i| format!("{}-{}", input, i))
-        .map(|input| {
-            let hash = knot_hash_bin(&input);
-            hash.chars()
-                .map(|c| match c {
-                    '1' => 1,
-                    '0' => 0,
-                    other => panic!("invalid bit '{}'", other),
-                })
-                .sum::<usize>()
-        })
-        .sum()
-}
+mod defrag;
+mod regions;
 
-#[derive(Default)]
-struct Connections {
-    nodes: HashMap<u32, Connection>,
-    node_id: u32,
+pub(crate) fn run() {
+    let args: Vec<String> = std::env::args().skip(2).collect();
+    match args.first().map(|arg| arg.as_str()) {
+        Some("regions") => return regions::run_regions(&args[1..]),
+        Some("defrag") => return defrag::run_defrag(&args[1..]),
+        _ => {}
+    }
+    let input = std::fs::read_to_string("input/input14.txt").unwrap();
+    let disk = DiskGrid::new(input.trim());
+    println!("used squares: {}", disk.used_squares());
+    println!("regions: {}", disk.regions());
 }
 
-#[derive(Default)]
-struct Connection {
-    children: HashSet<u32>,
-    parent: Option<u32>,
-}
+#[derive(Debug, Clone)]
+pub(crate) struct DiskGrid(BitGrid);
 
-impl Connections {
-    pub fn new_node(&mut self) -> u32 {
-        let id = self.node_id;
-        self.nodes.insert(id, Default::default());
-        self.node_id += 1;
-        id
+impl DiskGrid {
+    pub fn new(key: &str) -> Self {
+        Self::with_size(key, 128).unwrap()
     }
-    pub fn set_child(&mut self, parent: u32, child: u32) {
-        if parent == child {
-            return;
-        }
-        if let Some(super_parent) = self.nodes.get(&parent).unwrap().parent {
-            return self.set_child(super_parent, child);
+
+    pub fn with_size(key: &str, size: usize) -> anyhow::Result<Self> {
+        if !(1..=128).contains(&size) {
+            bail!("disk size must be between 1 and 128, got {}", size);
         }
-        let child_node = self.nodes.get_mut(&child).unwrap();
-        let grandchildren = std::mem::take(&mut child_node.children);
-        for &grandchild in grandchildren.iter() {
-            self.change_parent(grandchild, parent);
+        let hashes: Vec<[u8; 16]> = (0..size)
+            .into_par_iter()
+            .map(|row| knot_hash_bytes(format!("{}-{}", key, row).as_bytes()))
+            .collect();
+        let mut grid = BitGrid::new(size, size);
+        for (y, hash) in hashes.iter().enumerate() {
+            grid.set_row(y, hash);
         }
-        let parent_node = self.nodes.get_mut(&parent).unwrap();
-        parent_node.children.extend(grandchildren);
-        parent_node.children.insert(child);
+        Ok(Self(grid))
+    }
 
-        let mut child_node = self.nodes.get_mut(&child).unwrap();
-        if let Some(existing_parent) = child_node.parent {
-            return self.set_child(parent, existing_parent);
-        }
+    pub fn grid(&self) -> &BitGrid {
+        &self.0
+    }
 
-        child_node.parent = Some(parent);
+    pub fn used_squares(&self) -> usize {
+        self.0.count_ones()
     }
-    fn change_parent(&mut self, child: u32, new_parent: u32) {
-        let mut child_node = self.nodes.get_mut(&child).unwrap();
-        if child_node.parent.is_none() {
-            panic!("invalid state: child {} does not have parent", child);
-        }
-        child_node.parent = Some(new_parent);
+
+    pub fn regions(&self) -> usize {
+        count_regions(&self.0, Connectivity::default())
     }
-    pub fn get_parent_nodes_count(&self) -> usize {
-        self.nodes
-            .values()
-            .filter(|node| node.parent.is_none())
-            .count()
+
+    pub fn region_map(&self, connectivity: Connectivity) -> RegionMap {
+        RegionMap::new(&self.0, connectivity)
     }
 }
 
-fn count_regions(input: &str) -> usize {
-    let grid: [[bool; 128]; 128] = (0..128)
-        .map(|i| format!("{}-{}", input, i))
-        .map(|input| {
-            let hash = knot_hash_bin(&input);
-            hash.chars()
-                .map(|c| match c {
-                    '1' => true,
-                    '0' => false,
-                    other => panic!("invalid bit '{}'", other),
-                })
-                .collect::<Vec<_>>()
-                .try_into()
-                .unwrap()
-        })
-        .collect::<Vec<_>>()
-        .try_into()
-        .unwrap();
-
-    let mut connections = Connections::default();
-    let mut region_map: [[Option<u32>; 128]; 128] = [[None; 128]; 128];
-
-    if grid[0][0] {
-        region_map[0][0] = Some(connections.new_node());
-    }
-    for i in 1..128 {
-        if grid[0][i] {
-            let id = if let Some(id) = region_map[0][i - 1] {
-                id
-            } else {
-                connections.new_node()
-            };
-            region_map[0][i] = Some(id);
-        }
+#[derive(Debug, Copy, Clone, Default, Eq, PartialEq)]
+pub(crate) struct Connectivity {
+    diagonals: bool,
+    wrap: bool,
+}
+
+impl Connectivity {
+    pub fn with_diagonals(mut self) -> Self {
+        self.diagonals = true;
+        self
+    }
+
+    pub fn wrapping(mut self) -> Self {
+        self.wrap = true;
+        self
     }
 
-    for j in 1..128 {
-        if grid[j][0] {
-            let id = if let Some(id) = region_map[j - 1][0] {
-                id
-            } else {
-                connections.new_node()
-            };
-            region_map[j][0] = Some(id);
+    fn neighbor(&self, grid: &BitGrid, x: usize, y: usize, dx: isize, dy: isize) -> Option<usize> {
+        let (width, height) = (grid.width() as isize, grid.height() as isize);
+        let (mut x, mut y) = (x as isize + dx, y as isize + dy);
+        if self.wrap {
+            x = x.rem_euclid(width);
+            y = y.rem_euclid(height);
+        } else if !(0..width).contains(&x) || !(0..height).contains(&y) {
+            return None;
         }
-        for i in 1..128 {
-            if grid[j][i] {
-                let id1 = region_map[j][i - 1];
-                let id2 = region_map[j - 1][i];
-                let id = if let (Some(id1), Some(id2)) = (id1, id2) {
-                    connections.set_child(id2, id1);
-                    id2
-                } else if let Some(id) = id1 {
-                    id
-                } else if let Some(id) = id2 {
-                    id
-                } else {
-                    connections.new_node()
-                };
-                region_map[j][i] = Some(id);
+        Some((y * width + x) as usize)
+    }
+
+    // each neighbouring pair is visited once, from the square that comes later in row order
+    fn earlier_neighbors(
+        self,
+        grid: &BitGrid,
+        x: usize,
+        y: usize,
+    ) -> impl Iterator<Item = usize> + '_ {
+        const OFFSETS: [(isize, isize); 4] = [(-1, 0), (0, -1), (-1, -1), (1, -1)];
+        let count = if self.diagonals { 4 } else { 2 };
+        OFFSETS[..count]
+            .iter()
+            .filter_map(move |&(dx, dy)| self.neighbor(grid, x, y, dx, dy))
+    }
+}
+
+pub(crate) fn union_regions(grid: &BitGrid, connectivity: Connectivity) -> UnionFind {
+    let width = grid.width();
+    let mut regions = UnionFind::new(width * grid.height());
+    for y in 0..grid.height() {
+        for x in 0..width {
+            if !grid.get(x, y) {
+                continue;
+            }
+            for neighbor in connectivity.earlier_neighbors(grid, x, y) {
+                if grid.get(neighbor % width, neighbor / width) {
+                    regions.union(y * width + x, neighbor);
+                }
             }
         }
     }
+    regions
+}
 
-    connections.get_parent_nodes_count()
+pub(crate) fn count_regions(grid: &BitGrid, connectivity: Connectivity) -> usize {
+    let unused = grid.width() * grid.height() - grid.count_ones();
+    union_regions(grid, connectivity).sets() - unused
 }
 
 #[cfg(test)]
 mod tests {
+    use std::collections::VecDeque;
+
+    use proptest::prelude::*;
+
     use super::*;
 
+    fn flood_fill_regions(grid: &BitGrid, diagonals: bool, wrap: bool) -> usize {
+        let (width, height) = (grid.width() as isize, grid.height() as isize);
+        let mut seen = vec![vec![false; grid.width()]; grid.height()];
+        let mut regions = 0;
+        for y in 0..height {
+            for x in 0..width {
+                if !grid.get(x as usize, y as usize) || seen[y as usize][x as usize] {
+                    continue;
+                }
+                regions += 1;
+                seen[y as usize][x as usize] = true;
+                let mut next = VecDeque::from([(x, y)]);
+                while let Some((x, y)) = next.pop_front() {
+                    for dy in -1..=1 {
+                        for dx in -1..=1 {
+                            if (dx == 0 && dy == 0) || (!diagonals && dx != 0 && dy != 0) {
+                                continue;
+                            }
+                            let (mut x, mut y) = (x + dx, y + dy);
+                            if wrap {
+                                x = x.rem_euclid(width);
+                                y = y.rem_euclid(height);
+                            }
+                            if (0..width).contains(&x)
+                                && (0..height).contains(&y)
+                                && grid.get(x as usize, y as usize)
+                                && !seen[y as usize][x as usize]
+                            {
+                                seen[y as usize][x as usize] = true;
+                                next.push_back((x, y));
+                            }
+                        }
+                    }
+                }
+            }
+        }
+        regions
+    }
+
     #[test]
     fn test1() {
         let input = "flqrgnkx";
-        assert_eq!(count_used_squares(input), 8108);
+        assert_eq!(DiskGrid::new(input).used_squares(), 8108);
     }
     #[test]
     fn test2() {
         let input = "flqrgnkx";
-        assert_eq!(count_regions(input), 1242);
+        assert_eq!(DiskGrid::new(input).regions(), 1242);
+    }
+    #[test]
+    fn test_sizes() {
+        let disk = DiskGrid::with_size("flqrgnkx", 8).unwrap();
+        assert_eq!(
+            "##.#.#..
+.#.#.#.#
+....#.#.
+#.#.##.#
+.##.#...
+##..#..#
+.#...#..
+##.#.##.",
+            disk.grid().to_string()
+        );
+        assert_eq!(29, disk.used_squares());
+        assert_eq!(12, disk.regions());
+        assert!(DiskGrid::with_size("flqrgnkx", 0).is_err());
+        assert!(DiskGrid::with_size("flqrgnkx", 129).is_err());
+    }
+
+    #[test]
+    fn test_connectivity() {
+        let grid: BitGrid = "#...#
+.#...
+..#..
+.....
+#...#"
+            .parse()
+            .unwrap();
+        let four = Connectivity::default();
+        assert_eq!(6, count_regions(&grid, four));
+        assert_eq!(4, count_regions(&grid, four.with_diagonals()));
+        assert_eq!(3, count_regions(&grid, four.wrapping()));
+        assert_eq!(1, count_regions(&grid, four.with_diagonals().wrapping()));
+
+        let stripes: BitGrid = "#.#\n#.#".parse().unwrap();
+        assert_eq!(2, count_regions(&stripes, four));
+        assert_eq!(1, count_regions(&stripes, four.wrapping()));
+        assert_eq!(
+            1,
+            count_regions(&"#".parse().unwrap(), four.with_diagonals().wrapping())
+        );
+    }
+
+    proptest! {
+        #[test]
+        fn test_same_as_flood_fill(
+            (width, height, bits) in (1..20usize, 1..20usize).prop_flat_map(|(width, height)| {
+                (Just(width), Just(height), prop::collection::vec(any::<bool>(), width * height))
+            })
+        ) {
+            let mut grid = BitGrid::new(width, height);
+            for (i, &bit) in bits.iter().enumerate() {
+                grid.set(i % width, i / width, bit);
+            }
+            for diagonals in [false, true] {
+                for wrap in [false, true] {
+                    let mut connectivity = Connectivity::default();
+                    if diagonals {
+                        connectivity = connectivity.with_diagonals();
+                    }
+                    if wrap {
+                        connectivity = connectivity.wrapping();
+                    }
+                    prop_assert_eq!(
+                        flood_fill_regions(&grid, diagonals, wrap),
+                        count_regions(&grid, connectivity)
+                    );
+                }
+            }
+        }
     }
 }
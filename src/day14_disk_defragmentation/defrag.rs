@@ -0,0 +1,218 @@
+use super::regions::RegionMap;
+use super::{count_regions, Connectivity, DiskGrid};
+use crate::bit_grid::BitGrid;
+
+#[derive(Debug, Clone)]
+struct Shape {
+    cells: Vec<(usize, usize)>,
+    width: usize,
+    position: (usize, usize),
+}
+
+#[derive(Debug, Clone, Default, Eq, PartialEq)]
+pub(crate) struct DefragReport {
+    pub passes: usize,
+    pub region_moves: usize,
+    pub square_moves: usize,
+}
+
+// regions are moved as rigid shapes and never placed next to another region,
+// so every region stays contiguous and the region count does not change
+pub(crate) struct Defragmenter {
+    grid: BitGrid,
+    shapes: Vec<Shape>,
+}
+
+impl Defragmenter {
+    pub fn new(grid: &BitGrid) -> Self {
+        let map = RegionMap::new(grid, Connectivity::default());
+        let mut shapes: Vec<Shape> = map
+            .regions()
+            .iter()
+            .map(|region| Shape {
+                cells: vec![],
+                width: region.bottom_right.0 - region.top_left.0 + 1,
+                position: region.top_left,
+            })
+            .collect();
+        for y in 0..grid.height() {
+            for x in 0..grid.width() {
+                if let Some(id) = map.label(x, y) {
+                    let shape = &mut shapes[id];
+                    shape
+                        .cells
+                        .push((x - shape.position.0, y - shape.position.1));
+                }
+            }
+        }
+        Self {
+            grid: grid.clone(),
+            shapes,
+        }
+    }
+
+    pub fn grid(&self) -> &BitGrid {
+        &self.grid
+    }
+
+    fn fits(&self, shape: &Shape, (x, y): (usize, usize)) -> bool {
+        shape.cells.iter().all(|&(dx, dy)| {
+            let (x, y) = (x + dx, y + dy);
+            !self.grid.get(x, y)
+                && (x == 0 || !self.grid.get(x - 1, y))
+                && (y == 0 || !self.grid.get(x, y - 1))
+                && (x + 1 == self.grid.width() || !self.grid.get(x + 1, y))
+                && (y + 1 == self.grid.height() || !self.grid.get(x, y + 1))
+        })
+    }
+
+    fn place(&mut self, id: usize, value: bool) {
+        let shape = &self.shapes[id];
+        let (x, y) = shape.position;
+        for (dx, dy) in shape.cells.clone() {
+            self.grid.set(x + dx, y + dy, value);
+        }
+    }
+
+    pub fn pass(&mut self) -> DefragReport {
+        let mut report = DefragReport {
+            passes: 1,
+            ..Default::default()
+        };
+        for id in 0..self.shapes.len() {
+            self.place(id, false);
+            let shape = &self.shapes[id];
+            let (current_x, current_y) = shape.position;
+            let target = (0..=current_y)
+                .flat_map(|y| {
+                    let last_x = if y == current_y {
+                        current_x
+                    } else {
+                        self.grid.width() - shape.width
+                    };
+                    (0..=last_x).map(move |x| (x, y))
+                })
+                .find(|&position| self.fits(shape, position))
+                .unwrap_or(shape.position);
+            if target != shape.position {
+                report.region_moves += 1;
+                report.square_moves += shape.cells.len();
+                self.shapes[id].position = target;
+            }
+            self.place(id, true);
+        }
+        report
+    }
+
+    pub fn run(&mut self) -> DefragReport {
+        let mut total = DefragReport::default();
+        loop {
+            let report = self.pass();
+            total.passes += report.passes;
+            total.region_moves += report.region_moves;
+            total.square_moves += report.square_moves;
+            if report.region_moves == 0 {
+                return total;
+            }
+        }
+    }
+}
+
+pub(super) fn run_defrag(args: &[String]) {
+    let key = match args.first() {
+        Some(key) => key.clone(),
+        None => std::fs::read_to_string("input/input14.txt")
+            .unwrap()
+            .trim()
+            .to_string(),
+    };
+    let disk = DiskGrid::new(&key);
+    let mut defragmenter = Defragmenter::new(disk.grid());
+    let report = defragmenter.run();
+    println!("{}\n", defragmenter.grid());
+    println!(
+        "moved {} regions ({} squares) in {} passes",
+        report.region_moves, report.square_moves, report.passes
+    );
+    println!(
+        "regions before: {}, after: {}",
+        disk.regions(),
+        count_regions(defragmenter.grid(), Connectivity::default())
+    );
+}
+
+#[cfg(test)]
+mod tests {
+    use proptest::prelude::*;
+
+    use super::*;
+
+    fn region_sizes(grid: &BitGrid) -> Vec<usize> {
+        let mut sizes: Vec<usize> = RegionMap::new(grid, Connectivity::default())
+            .regions()
+            .iter()
+            .map(|region| region.size)
+            .collect();
+        sizes.sort_unstable();
+        sizes
+    }
+
+    #[test]
+    fn test_defrag() {
+        let grid: BitGrid = "......
+..#...
+......
+...##.
+....#.
+.....#"
+            .parse()
+            .unwrap();
+        let mut defragmenter = Defragmenter::new(&grid);
+        assert_eq!(
+            DefragReport {
+                passes: 2,
+                region_moves: 3,
+                square_moves: 5
+            },
+            defragmenter.run()
+        );
+        assert_eq!(
+            "#.##.#
+...#..
+......
+......
+......
+......",
+            defragmenter.grid().to_string()
+        );
+    }
+
+    #[test]
+    fn test_disk() {
+        let disk = DiskGrid::with_size("flqrgnkx", 32).unwrap();
+        let mut defragmenter = Defragmenter::new(disk.grid());
+        let report = defragmenter.run();
+        assert!(report.region_moves > 0);
+        assert_eq!(disk.used_squares(), defragmenter.grid().count_ones());
+        assert_eq!(region_sizes(disk.grid()), region_sizes(defragmenter.grid()));
+        assert_eq!(0, defragmenter.pass().region_moves);
+    }
+
+    proptest! {
+        #[test]
+        fn test_keeps_regions(
+            (width, height, bits) in (1..12usize, 1..12usize).prop_flat_map(|(width, height)| {
+                (Just(width), Just(height), prop::collection::vec(any::<bool>(), width * height))
+            })
+        ) {
+            let mut grid = BitGrid::new(width, height);
+            for (i, &bit) in bits.iter().enumerate() {
+                grid.set(i % width, i / width, bit);
+            }
+            let mut defragmenter = Defragmenter::new(&grid);
+            defragmenter.run();
+            prop_assert_eq!(grid.count_ones(), defragmenter.grid().count_ones());
+            prop_assert_eq!(region_sizes(&grid), region_sizes(defragmenter.grid()));
+        }
+    }
+}
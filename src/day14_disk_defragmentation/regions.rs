@@ -0,0 +1,211 @@
+use std::collections::HashMap;
+
+use super::{union_regions, Connectivity, DiskGrid};
+use crate::bit_grid::BitGrid;
+
+// the bounds are taken over the squares as laid out on the grid, so with a wrapping
+// connectivity a region that crosses an edge can span the whole width or height
+#[derive(Debug, Clone, Eq, PartialEq)]
+pub(crate) struct Region {
+    pub id: usize,
+    pub size: usize,
+    pub top_left: (usize, usize),
+    pub bottom_right: (usize, usize),
+}
+
+#[derive(Debug, Clone)]
+pub(crate) struct RegionMap {
+    width: usize,
+    height: usize,
+    labels: Vec<Option<usize>>,
+    regions: Vec<Region>,
+}
+
+impl RegionMap {
+    pub fn new(grid: &BitGrid, connectivity: Connectivity) -> Self {
+        let (width, height) = (grid.width(), grid.height());
+        let mut sets = union_regions(grid, connectivity);
+        let mut ids = HashMap::new();
+        let mut regions: Vec<Region> = vec![];
+        let mut labels = vec![None; width * height];
+        for y in 0..height {
+            for x in 0..width {
+                if !grid.get(x, y) {
+                    continue;
+                }
+                let id = *ids.entry(sets.find(y * width + x)).or_insert_with(|| {
+                    regions.push(Region {
+                        id: regions.len(),
+                        size: 0,
+                        top_left: (x, y),
+                        bottom_right: (x, y),
+                    });
+                    regions.len() - 1
+                });
+                let region = &mut regions[id];
+                region.size += 1;
+                region.top_left = (region.top_left.0.min(x), region.top_left.1.min(y));
+                region.bottom_right = (region.bottom_right.0.max(x), region.bottom_right.1.max(y));
+                labels[y * width + x] = Some(id);
+            }
+        }
+        Self {
+            width,
+            height,
+            labels,
+            regions,
+        }
+    }
+
+    pub fn label(&self, x: usize, y: usize) -> Option<usize> {
+        self.labels[y * self.width + x]
+    }
+
+    pub fn regions(&self) -> &[Region] {
+        &self.regions
+    }
+
+    fn rows(&self) -> impl Iterator<Item = &[Option<usize>]> {
+        self.labels.chunks(self.width).take(self.height)
+    }
+
+    pub fn to_ids_string(&self) -> String {
+        let digits = self.regions.len().saturating_sub(1).to_string().len();
+        self.rows()
+            .map(|row| {
+                row.iter()
+                    .map(|label| match label {
+                        Some(id) => format!("{:>width$}", id, width = digits),
+                        None => format!("{:>width$}", '.', width = digits),
+                    })
+                    .collect::<Vec<_>>()
+                    .join(" ")
+            })
+            .collect::<Vec<_>>()
+            .join("\n")
+    }
+
+    pub fn to_colored_string(&self) -> String {
+        self.rows()
+            .map(|row| {
+                row.iter()
+                    .map(|label| match label {
+                        Some(id) => {
+                            let [r, g, b] = color(*id);
+                            format!("\x1b[48;2;{};{};{}m  \x1b[0m", r, g, b)
+                        }
+                        None => "  ".to_string(),
+                    })
+                    .collect::<String>()
+            })
+            .collect::<Vec<_>>()
+            .join("\n")
+    }
+}
+
+fn color(id: usize) -> [u8; 3] {
+    let mut hash = (id as u64 + 1).wrapping_mul(0x9e3779b97f4a7c15);
+    hash ^= hash >> 29;
+    let channel = |shift: u64| 64 + ((hash >> shift) & 0xff) as u8 % 192;
+    [channel(0), channel(8), channel(16)]
+}
+
+pub(super) fn run_regions(args: &[String]) {
+    const USAGE: &str = "usage: regions [ids|color] [key] [--diagonal] [--wrap]";
+    let mut connectivity = Connectivity::default();
+    let mut positional = vec![];
+    for arg in args {
+        match arg.as_str() {
+            "--diagonal" => connectivity = connectivity.with_diagonals(),
+            "--wrap" => connectivity = connectivity.wrapping(),
+            _ => positional.push(arg),
+        }
+    }
+    let args = positional;
+    let key = match args.get(1) {
+        Some(key) => key.to_string(),
+        None => std::fs::read_to_string("input/input14.txt")
+            .unwrap()
+            .trim()
+            .to_string(),
+    };
+    let map = DiskGrid::new(&key).region_map(connectivity);
+    match args.first().map(|arg| arg.as_str()).unwrap_or("color") {
+        "ids" => println!("{}", map.to_ids_string()),
+        "color" => println!("{}", map.to_colored_string()),
+        other => panic!("unknown output '{}'\n{}", other, USAGE),
+    }
+    let largest = map.regions().iter().max_by_key(|region| region.size);
+    println!("{} regions", map.regions().len());
+    if let Some(region) = largest {
+        println!(
+            "largest region {} has {} squares from {:?} to {:?}",
+            region.id, region.size, region.top_left, region.bottom_right
+        );
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_labels() {
+        let grid = "##.#
+.#.#
+#..#
+####"
+            .parse()
+            .unwrap();
+        let map = RegionMap::new(&grid, Connectivity::default());
+        assert_eq!(
+            "0 0 . 1
+. 0 . 1
+1 . . 1
+1 1 1 1",
+            map.to_ids_string()
+        );
+        assert_eq!(Some(1), map.label(0, 2));
+        assert_eq!(None, map.label(2, 0));
+        assert_eq!(
+            &[
+                Region {
+                    id: 0,
+                    size: 3,
+                    top_left: (0, 0),
+                    bottom_right: (1, 1)
+                },
+                Region {
+                    id: 1,
+                    size: 8,
+                    top_left: (0, 0),
+                    bottom_right: (3, 3)
+                }
+            ],
+            map.regions()
+        );
+        assert_eq!(4, map.to_colored_string().lines().count());
+        assert!(map.to_colored_string().contains("\x1b[48;2;"));
+
+        let map = RegionMap::new(&grid, Connectivity::default().with_diagonals());
+        assert_eq!(1, map.regions().len());
+        let map = RegionMap::new(&grid, Connectivity::default().wrapping());
+        assert_eq!(
+            "0 0 . 0
+. 0 . 0
+0 . . 0
+0 0 0 0",
+            map.to_ids_string()
+        );
+        assert_eq!((0, 0), map.regions()[0].top_left);
+        assert_eq!((3, 3), map.regions()[0].bottom_right);
+    }
+
+    #[test]
+    fn test_disk() {
+        let map = DiskGrid::new("flqrgnkx").region_map(Connectivity::default());
+        assert_eq!(1242, map.regions().len());
+        assert_eq!(8108, map.regions().iter().map(|r| r.size).sum::<usize>());
+        assert_eq!(128 * 128, map.to_ids_string().split_whitespace().count());
+    }
+}
@@ -3,7 +3,29 @@ use std::fmt::{Display, Formatter};
 use std::ops::AddAssign;
 use std::str::FromStr;
 
+use enum_dispatch::enum_dispatch;
+
+use dense_nodes::DenseNodes;
+
+mod batch;
+mod dense_nodes;
+mod highway;
+mod render;
+mod turmite;
+
 pub(crate) fn run() {
+    let args: Vec<String> = std::env::args().skip(2).collect();
+    match args.first().map(|arg| arg.as_str()) {
+        Some("batch") => batch::run_batch(&args[1..]),
+        Some("turmite") => turmite::run_turmite(&args[1..]),
+        Some("carriers") => turmite::run_carriers(&args[1..]),
+        Some("render") => render::run_render(&args[1..]),
+        Some("highway") => highway::run_highway(&args[1..]),
+        _ => run_puzzle(),
+    }
+}
+
+fn run_puzzle() {
     let input = std::fs::read_to_string("input/input22.txt").unwrap();
     let mut grid: Grid = input.parse().unwrap();
     grid.steps(10_000);
@@ -13,7 +35,7 @@ pub(crate) fn run() {
     );
 
     let grid: Grid = input.parse().unwrap();
-    let mut grid: GridV2 = grid.into();
+    let mut grid = GridV2::from_grid(grid, DenseNodes::new().into());
     grid.steps(10_000_000);
     println!(
         "bursts causing infection v2: {}",
@@ -30,12 +52,17 @@ struct Grid {
 }
 
 impl Grid {
+    pub fn new(infected: HashSet<Coord>) -> Self {
+        Grid {
+            infected,
+            direction: Direction::Up,
+            position: Coord::new(0, 0),
+            bursts_causing_infection: 0,
+            step: 0,
+        }
+    }
     pub fn steps(&mut self, count: usize) {
         for _ in 0..count {
-            // println!("step {}\n{}\n", self.step, self);
-            // println!("press enter to continue");
-            // std::io::stdin().read_line(&mut String::new()).unwrap();
-
             self.step();
         }
     }
@@ -54,14 +81,61 @@ impl Grid {
     }
 }
 
+type Node = u8;
+
+#[derive(Debug, Copy, Clone, Eq, PartialEq)]
 enum NodeState {
+    Clean,
     Weakened,
     Infected,
     Flagged,
 }
 
+impl From<Node> for NodeState {
+    fn from(value: Node) -> Self {
+        match value {
+            0 => NodeState::Clean,
+            1 => NodeState::Weakened,
+            2 => NodeState::Infected,
+            _ => NodeState::Flagged,
+        }
+    }
+}
+
+#[enum_dispatch]
+trait NodeStore {
+    fn get(&self, coord: &Coord) -> Node;
+    fn set(&mut self, coord: &Coord, node: Node);
+    fn entries(&self) -> Vec<(Coord, Node)>;
+}
+
+#[enum_dispatch(NodeStore)]
+enum Nodes {
+    HashMapNodes,
+    DenseNodes,
+}
+
+#[derive(Default)]
+struct HashMapNodes(HashMap<Coord, Node>);
+
+impl NodeStore for HashMapNodes {
+    fn get(&self, coord: &Coord) -> Node {
+        self.0.get(coord).copied().unwrap_or_default()
+    }
+    fn set(&mut self, coord: &Coord, node: Node) {
+        if node == 0 {
+            self.0.remove(coord);
+        } else {
+            self.0.insert(coord.clone(), node);
+        }
+    }
+    fn entries(&self) -> Vec<(Coord, Node)> {
+        self.0.iter().map(|(c, &node)| (c.clone(), node)).collect()
+    }
+}
+
 struct GridV2 {
-    node_states: HashMap<Coord, NodeState>,
+    node_states: Nodes,
     direction: Direction,
     position: Coord,
     bursts_causing_infection: usize,
@@ -69,33 +143,43 @@ struct GridV2 {
 }
 
 impl GridV2 {
+    pub fn from_grid(value: Grid, mut node_states: Nodes) -> Self {
+        for c in value.infected.iter() {
+            node_states.set(c, NodeState::Infected as Node);
+        }
+        Self {
+            node_states,
+            direction: value.direction,
+            position: value.position,
+            bursts_causing_infection: value.bursts_causing_infection,
+            step: value.step,
+        }
+    }
     pub fn steps(&mut self, count: usize) {
         for _ in 0..count {
             self.step();
         }
     }
     fn step(&mut self) {
-        match self.node_states.get(&self.position) {
-            None => {
-                self.node_states
-                    .insert(self.position.clone(), NodeState::Weakened);
+        let next = match self.node_states.get(&self.position).into() {
+            NodeState::Clean => {
                 self.direction = self.direction.turn_left();
+                NodeState::Weakened
             }
-            Some(NodeState::Weakened) => {
-                self.node_states
-                    .insert(self.position.clone(), NodeState::Infected);
+            NodeState::Weakened => {
                 self.bursts_causing_infection += 1;
+                NodeState::Infected
             }
-            Some(NodeState::Infected) => {
-                self.node_states
-                    .insert(self.position.clone(), NodeState::Flagged);
+            NodeState::Infected => {
                 self.direction = self.direction.turn_right();
+                NodeState::Flagged
             }
-            Some(NodeState::Flagged) => {
-                self.node_states.remove(&self.position);
+            NodeState::Flagged => {
                 self.direction = self.direction.turn_right().turn_right();
+                NodeState::Clean
             }
-        }
+        };
+        self.node_states.set(&self.position, next as Node);
         self.position += self.direction.into();
         self.step += 1;
     }
@@ -103,17 +187,7 @@ impl GridV2 {
 
 impl From<Grid> for GridV2 {
     fn from(value: Grid) -> Self {
-        Self {
-            node_states: value
-                .infected
-                .into_iter()
-                .map(|c| (c, NodeState::Infected))
-                .collect(),
-            direction: value.direction,
-            position: value.position,
-            bursts_causing_infection: value.bursts_causing_infection,
-            step: value.step,
-        }
+        Self::from_grid(value, HashMapNodes::default().into())
     }
 }
 
@@ -167,7 +241,7 @@ impl Coord {
     }
 }
 
-#[derive(Debug, Clone, Copy)]
+#[derive(Debug, Clone, Copy, Eq, PartialEq, Hash)]
 enum Direction {
     Up,
     Down,
@@ -216,13 +290,7 @@ impl FromStr for Grid {
                 infected.insert(Coord::new(x as i64 - n, y as i64 - n));
             }
         }
-        Ok(Grid {
-            infected,
-            direction: Direction::Up,
-            position: Coord::new(0, 0),
-            bursts_causing_infection: 0,
-            step: 0,
-        })
+        Ok(Grid::new(infected))
     }
 }
 
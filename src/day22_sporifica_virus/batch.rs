@@ -0,0 +1,236 @@
+use std::collections::HashSet;
+
+use rayon::prelude::*;
+
+use super::{Coord, Grid, GridV2};
+
+#[derive(Debug, Copy, Clone)]
+pub(super) enum Model {
+    V1,
+    V2,
+}
+
+impl Model {
+    pub fn simulate(&self, grid: Grid, bursts: usize) -> usize {
+        match self {
+            Model::V1 => {
+                let mut grid = grid;
+                grid.steps(bursts);
+                grid.bursts_causing_infection
+            }
+            Model::V2 => {
+                let mut grid: GridV2 = grid.into();
+                grid.steps(bursts);
+                grid.bursts_causing_infection
+            }
+        }
+    }
+}
+
+#[derive(Debug)]
+pub(super) struct Summary {
+    pub density: f64,
+    pub grids: usize,
+    pub mean: f64,
+    pub min: usize,
+    pub max: usize,
+}
+
+impl Summary {
+    pub fn new(density: f64, infections: &[usize]) -> Self {
+        Self {
+            density,
+            grids: infections.len(),
+            mean: infections.iter().sum::<usize>() as f64 / infections.len().max(1) as f64,
+            min: infections.iter().copied().min().unwrap_or_default(),
+            max: infections.iter().copied().max().unwrap_or_default(),
+        }
+    }
+}
+
+pub(super) fn run_batch(args: &[String]) {
+    const USAGE: &str =
+        "usage: batch <v1|v2> <bursts> (random <size> <samples> [seed] | <file>...)";
+    let model = match args.first().map(|arg| arg.as_str()) {
+        Some("v1") => Model::V1,
+        Some("v2") => Model::V2,
+        _ => panic!("{}", USAGE),
+    };
+    let bursts: usize = args
+        .get(1)
+        .expect(USAGE)
+        .parse()
+        .expect("bursts should be an integer");
+    let summaries = if args.get(2).map(|arg| arg.as_str()) == Some("random") {
+        let size = args
+            .get(3)
+            .expect(USAGE)
+            .parse()
+            .expect("size should be an integer");
+        let samples = args
+            .get(4)
+            .expect(USAGE)
+            .parse()
+            .expect("samples should be an integer");
+        let seed = args
+            .get(5)
+            .map(|arg| arg.parse().expect("seed should be an integer"))
+            .unwrap_or(1);
+        let densities: Vec<f64> = (0..=10).map(|i| i as f64 / 10.0).collect();
+        simulate_densities(model, bursts, size, &densities, samples, seed)
+    } else {
+        let grids: Vec<(f64, Grid)> = args[2..]
+            .iter()
+            .map(|path| {
+                let input = std::fs::read_to_string(path).unwrap();
+                let size = input.lines().count();
+                let grid: Grid = input.parse().unwrap();
+                (grid.infected.len() as f64 / (size * size) as f64, grid)
+            })
+            .collect();
+        let densities: Vec<f64> = grids.iter().map(|(density, _)| *density).collect();
+        let infections = simulate(
+            model,
+            bursts,
+            grids.into_iter().map(|(_, grid)| grid).collect(),
+        );
+        densities
+            .into_iter()
+            .zip(infections)
+            .map(|(density, infections)| Summary::new(density, &[infections]))
+            .collect()
+    };
+
+    println!("density  grids  mean infections  min  max");
+    for summary in summaries {
+        println!(
+            "{:7.3}  {:5}  {:15.1}  {}  {}",
+            summary.density, summary.grids, summary.mean, summary.min, summary.max
+        );
+    }
+}
+
+pub(super) fn simulate(model: Model, bursts: usize, grids: Vec<Grid>) -> Vec<usize> {
+    grids
+        .into_par_iter()
+        .map(|grid| model.simulate(grid, bursts))
+        .collect()
+}
+
+pub(super) fn simulate_densities(
+    model: Model,
+    bursts: usize,
+    size: usize,
+    densities: &[f64],
+    samples: usize,
+    seed: u64,
+) -> Vec<Summary> {
+    let grids: Vec<Grid> = densities
+        .iter()
+        .enumerate()
+        .flat_map(|(i, &density)| {
+            (0..samples).map(move |sample| {
+                let seed = seed
+                    .wrapping_mul(6364136223846793005)
+                    .wrapping_add((i * samples + sample) as u64);
+                random_grid(size, density, seed)
+            })
+        })
+        .collect();
+    let infections = simulate(model, bursts, grids);
+    densities
+        .iter()
+        .zip(infections.chunks(samples.max(1)))
+        .map(|(&density, infections)| Summary::new(density, infections))
+        .collect()
+}
+
+pub(super) fn random_grid(size: usize, density: f64, seed: u64) -> Grid {
+    let mut random = Random::new(seed);
+    let n = (size / 2) as i64;
+    let mut infected = HashSet::new();
+    for y in 0..size as i64 {
+        for x in 0..size as i64 {
+            if random.next_f64() < density {
+                infected.insert(Coord::new(x - n, y - n));
+            }
+        }
+    }
+    Grid::new(infected)
+}
+
+struct Random {
+    state: u64,
+}
+
+impl Random {
+    // seeds that differ only in their low bits would start xorshift on nearly the
+    // same sequence, so they are spread over all bits with splitmix64 first
+    pub fn new(seed: u64) -> Self {
+        let mut state = seed.wrapping_add(0x9e3779b97f4a7c15);
+        state = (state ^ (state >> 30)).wrapping_mul(0xbf58476d1ce4e5b9);
+        state = (state ^ (state >> 27)).wrapping_mul(0x94d049bb133111eb);
+        state ^= state >> 31;
+        // xorshift never leaves zero, and splitmix64 maps exactly one seed there
+        Self {
+            state: if state == 0 {
+                0x9e3779b97f4a7c15
+            } else {
+                state
+            },
+        }
+    }
+
+    pub fn next_f64(&mut self) -> f64 {
+        self.state ^= self.state << 13;
+        self.state ^= self.state >> 7;
+        self.state ^= self.state << 17;
+        (self.state >> 11) as f64 / (1u64 << 53) as f64
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_simulate() {
+        let input = "..#
+#..
+...";
+        let grids = (0..4).map(|_| input.parse().unwrap()).collect();
+        assert_eq!(vec![5587; 4], simulate(Model::V1, 10_000, grids));
+        let grids = (0..4).map(|_| input.parse().unwrap()).collect();
+        assert_eq!(vec![26; 4], simulate(Model::V2, 100, grids));
+    }
+
+    #[test]
+    fn test_random_grid() {
+        assert!(random_grid(25, 0.0, 1).infected.is_empty());
+        assert_eq!(625, random_grid(25, 1.0, 1).infected.len());
+        let infected = random_grid(101, 0.3, 7).infected.len();
+        assert!((2_600..3_500).contains(&infected));
+        assert_eq!(
+            random_grid(25, 0.5, 3).infected,
+            random_grid(25, 0.5, 3).infected
+        );
+
+        for seed in [0, 1, 41] {
+            let grid = random_grid(32, 0.5, seed).infected;
+            let next = random_grid(32, 0.5, seed + 1).infected;
+            assert!(
+                grid != next,
+                "seeds {} and {} give the same grid",
+                seed,
+                seed + 1
+            );
+            let shared = grid.intersection(&next).count() as f64;
+            assert!((0.15..0.35).contains(&(shared / (32 * 32) as f64)));
+        }
+
+        let summaries = simulate_densities(Model::V1, 1_000, 25, &[0.0, 0.5], 3, 1);
+        assert_eq!(2, summaries.len());
+        assert_eq!(3, summaries[1].grids);
+        assert!(summaries[1].min <= summaries[1].max);
+    }
+}
@@ -0,0 +1,177 @@
+use super::{Coord, Node, NodeStore};
+
+const CHUNK: i64 = 64;
+
+pub(super) struct DenseNodes {
+    min: Coord,
+    width: i64,
+    height: i64,
+    nodes: Vec<Node>,
+}
+
+impl DenseNodes {
+    pub fn new() -> Self {
+        Self {
+            min: Coord::new(-CHUNK / 2, -CHUNK / 2),
+            width: CHUNK,
+            height: CHUNK,
+            nodes: vec![0; (CHUNK * CHUNK) as usize],
+        }
+    }
+    fn index(&self, coord: &Coord) -> Option<usize> {
+        let x = coord.x - self.min.x;
+        let y = coord.y - self.min.y;
+        if x < 0 || y < 0 || x >= self.width || y >= self.height {
+            None
+        } else {
+            Some((y * self.width + x) as usize)
+        }
+    }
+    // each side that has to grow is extended by at least the current size of the
+    // grid, so a walk that keeps leaving the grid only copies it O(log n) times
+    fn grow_to(&mut self, coord: &Coord) {
+        let grow = |needed: i64, size: i64| {
+            if needed > 0 {
+                (needed.max(size) + CHUNK - 1) / CHUNK * CHUNK
+            } else {
+                0
+            }
+        };
+        let before_x = grow(self.min.x - coord.x, self.width);
+        let after_x = grow(coord.x - (self.min.x + self.width - 1), self.width);
+        let before_y = grow(self.min.y - coord.y, self.height);
+        let after_y = grow(coord.y - (self.min.y + self.height - 1), self.height);
+        let min = Coord::new(self.min.x - before_x, self.min.y - before_y);
+        let width = self.width + before_x + after_x;
+        let height = self.height + before_y + after_y;
+
+        let mut nodes = vec![0; (width * height) as usize];
+        let offset_x = (self.min.x - min.x) as usize;
+        for (y, row) in self.nodes.chunks(self.width as usize).enumerate() {
+            let start = (y + (self.min.y - min.y) as usize) * width as usize + offset_x;
+            nodes[start..start + row.len()].copy_from_slice(row);
+        }
+        self.min = min;
+        self.width = width;
+        self.height = height;
+        self.nodes = nodes;
+    }
+}
+
+impl NodeStore for DenseNodes {
+    fn get(&self, coord: &Coord) -> Node {
+        self.index(coord).map(|i| self.nodes[i]).unwrap_or_default()
+    }
+    fn set(&mut self, coord: &Coord, node: Node) {
+        let i = match self.index(coord) {
+            Some(i) => i,
+            None if node == 0 => return,
+            None => {
+                self.grow_to(coord);
+                self.index(coord).unwrap()
+            }
+        };
+        self.nodes[i] = node;
+    }
+    fn entries(&self) -> Vec<(Coord, Node)> {
+        self.nodes
+            .iter()
+            .enumerate()
+            .filter(|(_, &node)| node != 0)
+            .map(|(i, &node)| {
+                let i = i as i64;
+                (
+                    Coord::new(self.min.x + i % self.width, self.min.y + i / self.width),
+                    node,
+                )
+            })
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use utils::timer::Timer;
+
+    use super::super::{Grid, GridV2, HashMapNodes};
+    use super::*;
+
+    #[test]
+    fn test_grow() {
+        let mut nodes = DenseNodes::new();
+        let coords = [
+            Coord::new(0, 0),
+            Coord::new(-33, 5),
+            Coord::new(100, -200),
+            Coord::new(31, 31),
+            Coord::new(-1000, 1000),
+        ];
+        for (i, c) in coords.iter().enumerate() {
+            nodes.set(c, 1 + i as Node % 3);
+        }
+        for (i, c) in coords.iter().enumerate() {
+            assert_eq!(1 + i as Node % 3, nodes.get(c));
+        }
+        assert_eq!(0, nodes.get(&Coord::new(1, 0)));
+        assert_eq!(0, (nodes.width % CHUNK) + (nodes.height % CHUNK));
+    }
+
+    #[test]
+    fn test_grow_geometrically() {
+        let mut nodes = DenseNodes::new();
+        let mut reallocations = 0;
+        for x in 0..100_000 {
+            let width = nodes.width;
+            nodes.set(&Coord::new(x, 0), 1);
+            if nodes.width != width {
+                reallocations += 1;
+            }
+        }
+        assert!(reallocations <= 12, "{} reallocations", reallocations);
+        assert!(nodes.width < 4 * 100_000);
+        assert_eq!(100_000, nodes.entries().len());
+    }
+
+    #[test]
+    fn test_same_as_hash_map() {
+        let sample = "..#
+#..
+...";
+        let input = std::fs::read_to_string("input/input22.txt").unwrap();
+        for (input, steps) in [(sample, 10_000_000), (input.as_str(), 1_000_000)] {
+            let grid: Grid = input.parse().unwrap();
+            let mut expected: GridV2 = grid.into();
+            expected.steps(steps);
+            let grid: Grid = input.parse().unwrap();
+            let mut actual = GridV2::from_grid(grid, DenseNodes::new().into());
+            actual.steps(steps);
+            assert_eq!(
+                expected.bursts_causing_infection,
+                actual.bursts_causing_infection
+            );
+        }
+    }
+
+    #[test]
+    #[ignore]
+    fn benchmark() {
+        let input = std::fs::read_to_string("input/input22.txt").unwrap();
+        let expected = {
+            let _timer =
+                Timer::start(|elapsed| println!("hash map grid took {} ms.", elapsed.as_millis()));
+            let grid: Grid = input.parse().unwrap();
+            let mut grid = GridV2::from_grid(grid, HashMapNodes::default().into());
+            grid.steps(10_000_000);
+            grid.bursts_causing_infection
+        };
+        let actual = {
+            let _timer =
+                Timer::start(|elapsed| println!("dense grid took {} ms.", elapsed.as_millis()));
+            let grid: Grid = input.parse().unwrap();
+            let mut grid = GridV2::from_grid(grid, DenseNodes::new().into());
+            grid.steps(10_000_000);
+            grid.bursts_causing_infection
+        };
+        assert_eq!(expected, actual);
+    }
+}
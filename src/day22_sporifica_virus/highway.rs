@@ -0,0 +1,261 @@
+use std::collections::hash_map::DefaultHasher;
+use std::collections::{HashMap, HashSet};
+use std::hash::{Hash, Hasher};
+
+use anyhow::bail;
+
+use super::dense_nodes::DenseNodes;
+use super::turmite::{parse_rule, Turmite};
+use super::{Coord, Grid, NodeStore};
+
+const CONFIRMATIONS: usize = 3;
+
+#[derive(Debug, Clone, Eq, PartialEq)]
+pub(super) struct Highway {
+    pub start: usize,
+    pub period: usize,
+    pub shift: Coord,
+    pub infections: usize,
+}
+
+#[derive(Debug, Clone)]
+struct Record {
+    window: u64,
+    position: Coord,
+    bursts: usize,
+}
+
+pub(super) struct HighwayDetector {
+    radius: i64,
+    records: Vec<Record>,
+    last_seen: HashMap<u64, usize>,
+    candidate: Option<(usize, Coord, usize)>,
+    streak: usize,
+}
+
+impl HighwayDetector {
+    pub fn new(radius: i64) -> Self {
+        Self {
+            radius,
+            records: vec![],
+            last_seen: HashMap::new(),
+            candidate: None,
+            streak: 0,
+        }
+    }
+
+    pub fn observe(&mut self, turmite: &Turmite) -> Option<Highway> {
+        let carrier = &turmite.carriers[0];
+        let mut hasher = DefaultHasher::new();
+        carrier.direction.hash(&mut hasher);
+        for y in -self.radius..=self.radius {
+            for x in -self.radius..=self.radius {
+                let c = Coord::new(carrier.position.x + x, carrier.position.y + y);
+                turmite.nodes.get(&c).hash(&mut hasher);
+            }
+        }
+        let record = Record {
+            window: hasher.finish(),
+            position: carrier.position.clone(),
+            bursts: carrier.bursts_causing_infection,
+        };
+        let step = self.records.len();
+
+        let matches = |(period, shift, infections): &(usize, Coord, usize)| {
+            let earlier = &self.records[step - period];
+            earlier.window == record.window
+                && record.position.x - earlier.position.x == shift.x
+                && record.position.y - earlier.position.y == shift.y
+                && record.bursts - earlier.bursts == *infections
+        };
+        if self.candidate.as_ref().map(matches).unwrap_or(false) {
+            self.streak += 1;
+        } else {
+            self.streak = 1;
+            self.candidate = self.last_seen.get(&record.window).map(|&earlier| {
+                let earlier = &self.records[earlier];
+                (
+                    step - self.last_seen[&record.window],
+                    Coord::new(
+                        record.position.x - earlier.position.x,
+                        record.position.y - earlier.position.y,
+                    ),
+                    record.bursts - earlier.bursts,
+                )
+            });
+        }
+        self.last_seen.insert(record.window, step);
+        self.records.push(record);
+
+        match &self.candidate {
+            Some((period, shift, infections)) if self.streak >= period * CONFIRMATIONS => {
+                Some(Highway {
+                    start: step + 1 - self.streak,
+                    period: *period,
+                    shift: shift.clone(),
+                    infections: *infections,
+                })
+            }
+            _ => None,
+        }
+    }
+}
+
+// the window only covers the neighbourhood of the carrier, so a highway is only
+// extrapolated once every node on the road ahead was written by the highway itself
+fn clear_ahead(turmite: &Turmite, detector: &HighwayDetector, highway: &Highway) -> bool {
+    let position = &turmite.carriers[0].position;
+    let shift = &highway.shift;
+    let road = detector.radius * (shift.x.abs() + shift.y.abs());
+    let visited: HashSet<&Coord> = detector.records[highway.start..]
+        .iter()
+        .map(|record| &record.position)
+        .collect();
+    turmite.nodes.entries().iter().all(|(c, _)| {
+        let (dx, dy) = (c.x - position.x, c.y - position.y);
+        dx * shift.x + dy * shift.y <= 0
+            || (dx * shift.y - dy * shift.x).abs() > road
+            || visited.contains(c)
+    })
+}
+
+pub(super) fn bursts_after(
+    turmite: &mut Turmite,
+    steps: usize,
+    radius: i64,
+    search_limit: usize,
+) -> anyhow::Result<(usize, Option<Highway>)> {
+    if turmite.carriers.len() != 1 {
+        bail!("highways can only be extrapolated for a single carrier");
+    }
+    let mut detector = HighwayDetector::new(radius);
+    let mut next_check = 0;
+    for step in 0..steps.min(search_limit) {
+        match detector.observe(turmite) {
+            Some(highway) if step >= next_check => {
+                if clear_ahead(turmite, &detector, &highway) {
+                    let remaining = steps - step;
+                    turmite.steps(remaining % highway.period);
+                    return Ok((
+                        turmite.bursts_causing_infection()
+                            + remaining / highway.period * highway.infections,
+                        Some(highway),
+                    ));
+                }
+                next_check = step + highway.period;
+            }
+            _ => {}
+        }
+        turmite.steps(1);
+    }
+    turmite.steps(steps - steps.min(search_limit));
+    Ok((turmite.bursts_causing_infection(), None))
+}
+
+pub(super) fn run_highway(args: &[String]) {
+    const USAGE: &str = "usage: highway <rule|part1|part2> <bursts> [search limit] [radius] [file]";
+    let rule = parse_rule(args.first().expect(USAGE)).unwrap();
+    let bursts = args
+        .get(1)
+        .expect(USAGE)
+        .parse()
+        .expect("bursts should be an integer");
+    let search_limit = args
+        .get(2)
+        .map(|arg| arg.parse().expect("search limit should be an integer"))
+        .unwrap_or(1_000_000);
+    let radius = args
+        .get(3)
+        .map(|arg| arg.parse().expect("radius should be an integer"))
+        .unwrap_or(4);
+    let grid: Grid = match args.get(4) {
+        Some(path) => std::fs::read_to_string(path).unwrap().parse().unwrap(),
+        None => Grid::new(Default::default()),
+    };
+    let mut turmite = Turmite::new(rule, grid, DenseNodes::new().into());
+    let (infections, highway) = bursts_after(&mut turmite, bursts, radius, search_limit).unwrap();
+    match highway {
+        Some(highway) => println!(
+            "highway from burst {} with period {}, shift ({}, {}) and {} infections per period",
+            highway.start, highway.period, highway.shift.x, highway.shift.y, highway.infections
+        ),
+        None => println!("no highway found in the first {} bursts", search_limit),
+    }
+    println!("bursts causing infection: {}", infections);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::super::turmite::{Carrier, PART1_RULE};
+    use super::super::Direction;
+    use super::*;
+
+    fn langtons_ant() -> Turmite {
+        Turmite::new(
+            PART1_RULE.parse().unwrap(),
+            Grid::new(Default::default()),
+            DenseNodes::new().into(),
+        )
+    }
+
+    #[test]
+    fn test_langtons_ant() {
+        let (_, highway) = bursts_after(&mut langtons_ant(), 1_000_000_000_000, 4, 20_000).unwrap();
+        let highway = highway.unwrap();
+        assert_eq!(104, highway.period);
+        assert_eq!(2, highway.shift.x.abs());
+        assert_eq!(2, highway.shift.y.abs());
+        assert!((9_900..10_600).contains(&highway.start));
+
+        for steps in [5_000, 15_000, 30_001, 55_555] {
+            let mut simulated = langtons_ant();
+            simulated.steps(steps);
+            let (extrapolated, highway) =
+                bursts_after(&mut langtons_ant(), steps, 4, 20_000).unwrap();
+            assert_eq!(steps > 10_500, highway.is_some());
+            assert_eq!(simulated.bursts_causing_infection(), extrapolated);
+        }
+    }
+
+    #[test]
+    fn test_no_highway() {
+        let mut turmite = Turmite::new(
+            "RRLL".parse().unwrap(),
+            Grid::new(Default::default()),
+            DenseNodes::new().into(),
+        );
+        let (_, highway) = bursts_after(&mut turmite, 100_000, 4, 100_000).unwrap();
+        assert_eq!(None, highway);
+    }
+
+    #[test]
+    fn test_obstacle_ahead() {
+        let mut ant = langtons_ant();
+        let (_, highway) = bursts_after(&mut ant, 12_000, 4, 20_000).unwrap();
+        let highway = highway.unwrap();
+        let position = &ant.carriers[0].position;
+        let obstacle = Coord::new(
+            position.x + 20 * highway.shift.x,
+            position.y + 20 * highway.shift.y,
+        );
+
+        let with_obstacle = || {
+            let mut ant = langtons_ant();
+            ant.nodes.set(&obstacle, 1);
+            ant
+        };
+        let mut simulated = with_obstacle();
+        simulated.steps(30_000);
+        let (extrapolated, _) = bursts_after(&mut with_obstacle(), 30_000, 4, 20_000).unwrap();
+        assert_eq!(simulated.bursts_causing_infection(), extrapolated);
+    }
+
+    #[test]
+    fn test_multiple_carriers() {
+        let mut turmite = langtons_ant().with_carriers(vec![
+            Carrier::new(Coord::new(0, 0), Direction::Up),
+            Carrier::new(Coord::new(100, 0), Direction::Up),
+        ]);
+        assert!(bursts_after(&mut turmite, 1_000_000, 4, 20_000).is_err());
+    }
+}
@@ -0,0 +1,336 @@
+use std::borrow::Cow;
+
+use anyhow::bail;
+
+use super::dense_nodes::DenseNodes;
+use super::{Coord, Grid, GridV2, Node, NodeState, NodeStore};
+
+const PALETTE: [[u8; 3]; 5] = [
+    [16, 16, 16],
+    [230, 200, 40],
+    [220, 40, 40],
+    [60, 110, 230],
+    [40, 220, 80],
+];
+const CARRIER: usize = 4;
+
+pub(super) trait Simulation {
+    fn steps(&mut self, count: usize);
+    fn snapshot(&self) -> Snapshot;
+}
+
+impl Simulation for Grid {
+    fn steps(&mut self, count: usize) {
+        Grid::steps(self, count)
+    }
+    fn snapshot(&self) -> Snapshot {
+        Snapshot {
+            nodes: self
+                .infected
+                .iter()
+                .map(|c| (c.clone(), NodeState::Infected))
+                .collect(),
+            carrier: self.position.clone(),
+        }
+    }
+}
+
+impl Simulation for GridV2 {
+    fn steps(&mut self, count: usize) {
+        GridV2::steps(self, count)
+    }
+    fn snapshot(&self) -> Snapshot {
+        Snapshot {
+            nodes: self
+                .node_states
+                .entries()
+                .into_iter()
+                .map(|(c, node)| (c, node.into()))
+                .collect(),
+            carrier: self.position.clone(),
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+pub(super) struct Snapshot {
+    nodes: Vec<(Coord, NodeState)>,
+    carrier: Coord,
+}
+
+impl Snapshot {
+    pub fn bounds(&self) -> (Coord, Coord) {
+        self.nodes.iter().map(|(c, _)| c).fold(
+            (self.carrier.clone(), self.carrier.clone()),
+            |(min, max), c| {
+                (
+                    Coord::new(min.x.min(c.x), min.y.min(c.y)),
+                    Coord::new(max.x.max(c.x), max.y.max(c.y)),
+                )
+            },
+        )
+    }
+
+    fn colors(&self, min: &Coord, max: &Coord) -> Vec<Vec<usize>> {
+        let mut rows = vec![
+            vec![NodeState::Clean as usize; (max.x - min.x + 1) as usize];
+            (max.y - min.y + 1) as usize
+        ];
+        for (c, state) in self.nodes.iter() {
+            rows[(c.y - min.y) as usize][(c.x - min.x) as usize] = *state as usize;
+        }
+        rows
+    }
+
+    pub fn to_terminal(&self, colored: bool) -> String {
+        let (min, max) = self.bounds();
+        self.colors(&min, &max)
+            .iter()
+            .zip(min.y..)
+            .map(|(row, y)| {
+                row.iter()
+                    .zip(min.x..)
+                    .map(|(&color, x)| {
+                        let symbol = match NodeState::from(color as Node) {
+                            NodeState::Clean => '.',
+                            NodeState::Weakened => 'W',
+                            NodeState::Infected => '#',
+                            NodeState::Flagged => 'F',
+                        };
+                        let symbol = if colored && color != NodeState::Clean as usize {
+                            let [r, g, b] = PALETTE[color];
+                            format!("\x1b[38;2;{};{};{}m{}\x1b[0m", r, g, b, symbol)
+                        } else {
+                            symbol.to_string()
+                        };
+                        if self.carrier == Coord::new(x, y) {
+                            format!("[{}]", symbol)
+                        } else {
+                            format!(" {} ", symbol)
+                        }
+                    })
+                    .collect::<String>()
+            })
+            .collect::<Vec<_>>()
+            .join("\n")
+    }
+
+    pub fn to_ppm(&self, min: &Coord, max: &Coord, scale: usize) -> anyhow::Result<Vec<u8>> {
+        if scale == 0 {
+            bail!("scale must be at least 1");
+        }
+        let (width, height, pixels) = self.pixels(min, max, scale);
+        let mut image = format!("P6\n{} {}\n255\n", width, height).into_bytes();
+        image.extend(pixels.into_iter().flat_map(|color| PALETTE[color]));
+        Ok(image)
+    }
+
+    fn pixels(&self, min: &Coord, max: &Coord, scale: usize) -> (usize, usize, Vec<usize>) {
+        let mut rows = self.colors(min, max);
+        if (min.x..=max.x).contains(&self.carrier.x) && (min.y..=max.y).contains(&self.carrier.y) {
+            rows[(self.carrier.y - min.y) as usize][(self.carrier.x - min.x) as usize] = CARRIER;
+        }
+        let width = rows[0].len() * scale;
+        let pixels = rows
+            .iter()
+            .flat_map(|row| {
+                let row: Vec<usize> = row.iter().flat_map(|&color| vec![color; scale]).collect();
+                vec![row; scale].into_iter().flatten()
+            })
+            .collect::<Vec<_>>();
+        (width, pixels.len() / width, pixels)
+    }
+}
+
+pub(super) fn record(
+    simulation: &mut impl Simulation,
+    bursts: usize,
+    every: usize,
+) -> anyhow::Result<Vec<Snapshot>> {
+    if every == 0 {
+        bail!("snapshots should be taken at least every burst");
+    }
+    let mut snapshots = vec![simulation.snapshot()];
+    let mut done = 0;
+    while done < bursts {
+        let count = every.min(bursts - done);
+        simulation.steps(count);
+        done += count;
+        snapshots.push(simulation.snapshot());
+    }
+    Ok(snapshots)
+}
+
+pub(super) fn common_bounds(snapshots: &[Snapshot]) -> (Coord, Coord) {
+    snapshots
+        .iter()
+        .map(|snapshot| snapshot.bounds())
+        .reduce(|(min, max), (other_min, other_max)| {
+            (
+                Coord::new(min.x.min(other_min.x), min.y.min(other_min.y)),
+                Coord::new(max.x.max(other_max.x), max.y.max(other_max.y)),
+            )
+        })
+        .unwrap_or((Coord::new(0, 0), Coord::new(0, 0)))
+}
+
+pub(super) fn to_gif(snapshots: &[Snapshot], scale: usize, delay: u16) -> anyhow::Result<Vec<u8>> {
+    if scale == 0 {
+        bail!("scale must be at least 1");
+    }
+    let (min, max) = common_bounds(snapshots);
+    let width = (max.x - min.x + 1) as usize * scale;
+    let height = (max.y - min.y + 1) as usize * scale;
+    if width > u16::MAX as usize || height > u16::MAX as usize {
+        bail!(
+            "{}x{} pixels is too large for a gif, use a smaller scale",
+            width,
+            height
+        );
+    }
+    let palette: Vec<u8> = PALETTE.iter().flatten().copied().collect();
+    let mut image = Vec::new();
+    {
+        let mut encoder = gif::Encoder::new(&mut image, width as u16, height as u16, &palette)?;
+        encoder.set_repeat(gif::Repeat::Infinite)?;
+        for snapshot in snapshots {
+            let (_, _, pixels) = snapshot.pixels(&min, &max, scale);
+            let pixels: Vec<u8> = pixels.into_iter().map(|color| color as u8).collect();
+            let mut frame = gif::Frame {
+                width: width as u16,
+                height: height as u16,
+                delay,
+                ..gif::Frame::default()
+            };
+            frame.buffer = Cow::Owned(pixels);
+            encoder.write_frame(&frame)?;
+        }
+    }
+    Ok(image)
+}
+
+pub(super) fn run_render(args: &[String]) {
+    const USAGE: &str =
+        "usage: render <v1|v2> <bursts> [every] [terminal | ppm <directory> | gif <file>] [scale]";
+    let input = std::fs::read_to_string("input/input22.txt").unwrap();
+    let grid: Grid = input.parse().unwrap();
+    let bursts: usize = args
+        .get(1)
+        .expect(USAGE)
+        .parse()
+        .expect("bursts should be an integer");
+    let every = args
+        .get(2)
+        .map(|arg| arg.parse().expect("every should be an integer"))
+        .unwrap_or(bursts.max(1));
+    let snapshots = match args.first().map(|arg| arg.as_str()) {
+        Some("v1") => {
+            let mut grid = grid;
+            record(&mut grid, bursts, every)
+        }
+        Some("v2") => record(
+            &mut GridV2::from_grid(grid, DenseNodes::new().into()),
+            bursts,
+            every,
+        ),
+        _ => panic!("{}", USAGE),
+    }
+    .unwrap();
+    let scale = args
+        .get(5)
+        .map(|arg| arg.parse().expect("scale should be an integer"))
+        .unwrap_or(4);
+    match args.get(3).map(|arg| arg.as_str()).unwrap_or("terminal") {
+        "terminal" => {
+            for (i, snapshot) in snapshots.iter().enumerate() {
+                println!(
+                    "after {} bursts:\n{}\n",
+                    (i * every).min(bursts),
+                    snapshot.to_terminal(true)
+                );
+            }
+        }
+        "ppm" => {
+            let directory = args.get(4).expect(USAGE);
+            std::fs::create_dir_all(directory).unwrap();
+            let (min, max) = common_bounds(&snapshots);
+            for (i, snapshot) in snapshots.iter().enumerate() {
+                let path = format!("{}/frame_{:05}.ppm", directory, i);
+                std::fs::write(&path, snapshot.to_ppm(&min, &max, scale).unwrap()).unwrap();
+            }
+            println!("wrote {} frames to {}", snapshots.len(), directory);
+        }
+        "gif" => {
+            let path = args.get(4).expect(USAGE);
+            std::fs::write(path, to_gif(&snapshots, scale, 10).unwrap()).unwrap();
+            println!("wrote {} frames to {}", snapshots.len(), path);
+        }
+        other => panic!("unknown output '{}'\n{}", other, USAGE),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const INPUT: &str = "..#
+#..
+...";
+
+    #[test]
+    fn test_terminal() {
+        let mut grid: Grid = INPUT.parse().unwrap();
+        grid.steps(7);
+        assert_eq!(grid.to_string(), {
+            let n = grid
+                .infected
+                .iter()
+                .map(|c| c.y.abs().max(c.x.abs()))
+                .max()
+                .unwrap_or(0);
+            let mut snapshot = grid.snapshot();
+            snapshot.nodes.push((Coord::new(-n, -n), NodeState::Clean));
+            snapshot.nodes.push((Coord::new(n, n), NodeState::Clean));
+            snapshot.to_terminal(false)
+        });
+
+        let grid: Grid = INPUT.parse().unwrap();
+        let mut grid = GridV2::from_grid(grid, DenseNodes::new().into());
+        grid.steps(7);
+        assert_eq!(
+            " .  W  W  .  # \n[.] #  .  W  . ",
+            grid.snapshot().to_terminal(false)
+        );
+        assert!(grid.snapshot().to_terminal(true).contains("\x1b[38;2;"));
+    }
+
+    #[test]
+    fn test_frames() {
+        let mut grid: Grid = INPUT.parse().unwrap();
+        let snapshots = record(&mut grid, 70, 10).unwrap();
+        assert_eq!(8, snapshots.len());
+        let (min, max) = common_bounds(&snapshots);
+        let ppm = snapshots[7].to_ppm(&min, &max, 2).unwrap();
+        let width = (max.x - min.x + 1) * 2;
+        let height = (max.y - min.y + 1) * 2;
+        let header = format!("P6\n{} {}\n255\n", width, height);
+        assert!(ppm.starts_with(header.as_bytes()));
+        assert_eq!(header.len() + (width * height * 3) as usize, ppm.len());
+
+        let gif = to_gif(&snapshots, 2, 10).unwrap();
+        assert!(gif.starts_with(b"GIF89a"));
+        assert_eq!(Some(&0x3b), gif.last());
+    }
+
+    #[test]
+    fn test_invalid_recordings() {
+        let mut grid: Grid = INPUT.parse().unwrap();
+        assert!(record(&mut grid, 70, 0).is_err());
+        let snapshots = record(&mut grid, 70, 70).unwrap();
+        assert_eq!(2, snapshots.len());
+        assert!(to_gif(&snapshots, 10_000, 10).is_err());
+        assert!(to_gif(&snapshots, 0, 10).is_err());
+        let (min, max) = common_bounds(&snapshots);
+        assert!(snapshots[0].to_ppm(&min, &max, 0).is_err());
+    }
+}
@@ -0,0 +1,311 @@
+use std::str::FromStr;
+
+use anyhow::{bail, Context};
+
+use super::dense_nodes::DenseNodes;
+use super::{Coord, Direction, Grid, Node, NodeStore, Nodes};
+
+pub(super) const PART1_RULE: &str = "LR";
+pub(super) const PART2_RULE: &str = "LNRU:2";
+
+pub(super) fn run_turmite(args: &[String]) {
+    let rule = parse_rule(
+        args.first()
+            .expect("usage: turmite <rule|part1|part2> [bursts] [file]"),
+    )
+    .unwrap();
+    let bursts = args
+        .get(1)
+        .map(|arg| arg.parse().expect("bursts should be an integer"))
+        .unwrap_or(10_000_000);
+    let path = args
+        .get(2)
+        .map(|arg| arg.as_str())
+        .unwrap_or("input/input22.txt");
+    let input = std::fs::read_to_string(path).unwrap();
+    let mut turmite = Turmite::new(rule, input.parse().unwrap(), DenseNodes::new().into());
+    turmite.steps(bursts);
+    println!(
+        "bursts causing infection: {}",
+        turmite.bursts_causing_infection()
+    );
+}
+
+pub(super) fn run_carriers(args: &[String]) {
+    const USAGE: &str = "usage: carriers <rule|part1|part2> <bursts> <x,y,U|D|L|R>...";
+    let rule = parse_rule(args.first().expect(USAGE)).unwrap();
+    let bursts = args
+        .get(1)
+        .expect(USAGE)
+        .parse()
+        .expect("bursts should be an integer");
+    let carriers = args[2..]
+        .iter()
+        .map(|arg| arg.parse())
+        .collect::<anyhow::Result<Vec<Carrier>>>()
+        .unwrap();
+    let input = std::fs::read_to_string("input/input22.txt").unwrap();
+    let mut turmite = Turmite::new(rule, input.parse().unwrap(), DenseNodes::new().into())
+        .with_carriers(carriers);
+    turmite.steps(bursts);
+    for (i, carrier) in turmite.carriers.iter().enumerate() {
+        println!(
+            "carrier {} caused {} infections",
+            i, carrier.bursts_causing_infection
+        );
+    }
+    println!("total: {}", turmite.bursts_causing_infection());
+}
+
+pub(super) fn parse_rule(s: &str) -> anyhow::Result<Rule> {
+    match s {
+        "part1" => PART1_RULE,
+        "part2" => PART2_RULE,
+        rule => rule,
+    }
+    .parse()
+}
+
+#[derive(Debug, Copy, Clone, Eq, PartialEq)]
+enum Turn {
+    Left,
+    Right,
+    Straight,
+    Back,
+}
+
+impl Turn {
+    pub fn apply(&self, direction: Direction) -> Direction {
+        match self {
+            Turn::Left => direction.turn_left(),
+            Turn::Right => direction.turn_right(),
+            Turn::Straight => direction,
+            Turn::Back => direction.turn_right().turn_right(),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Eq, PartialEq)]
+pub(super) struct Rule {
+    turns: Vec<Turn>,
+    infected: Node,
+}
+
+impl FromStr for Rule {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let (turns, infected) = match s.split_once(':') {
+            Some((turns, infected)) => (
+                turns,
+                infected
+                    .parse()
+                    .with_context(|| format!("invalid infected state '{}'", infected))?,
+            ),
+            None => (s, 1),
+        };
+        let turns = turns
+            .chars()
+            .map(|c| {
+                Ok(match c {
+                    'L' => Turn::Left,
+                    'R' => Turn::Right,
+                    'N' => Turn::Straight,
+                    'U' => Turn::Back,
+                    other => bail!("invalid turn '{}'", other),
+                })
+            })
+            .collect::<anyhow::Result<Vec<_>>>()?;
+        if turns.len() < 2 || turns.len() > Node::MAX as usize + 1 {
+            bail!("rule '{}' should have between 2 and 256 node states", s);
+        }
+        if infected == 0 || infected as usize >= turns.len() {
+            bail!("rule '{}' has no node state {}", s, infected);
+        }
+        Ok(Self { turns, infected })
+    }
+}
+
+#[derive(Debug, Clone)]
+pub(super) struct Carrier {
+    pub direction: Direction,
+    pub position: Coord,
+    pub bursts_causing_infection: usize,
+}
+
+impl Carrier {
+    pub fn new(position: Coord, direction: Direction) -> Self {
+        Self {
+            direction,
+            position,
+            bursts_causing_infection: 0,
+        }
+    }
+}
+
+impl FromStr for Carrier {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let parts: Vec<&str> = s.split(',').collect();
+        if parts.len() != 3 {
+            bail!("carrier '{}' should look like x,y,direction", s);
+        }
+        let direction = match parts[2] {
+            "U" => Direction::Up,
+            "D" => Direction::Down,
+            "L" => Direction::Left,
+            "R" => Direction::Right,
+            other => bail!("invalid direction '{}'", other),
+        };
+        Ok(Self::new(
+            Coord::new(parts[0].parse()?, parts[1].parse()?),
+            direction,
+        ))
+    }
+}
+
+pub(super) struct Turmite {
+    rule: Rule,
+    pub nodes: Nodes,
+    pub carriers: Vec<Carrier>,
+}
+
+impl Turmite {
+    pub fn new(rule: Rule, grid: Grid, mut nodes: Nodes) -> Self {
+        for c in grid.infected.iter() {
+            nodes.set(c, rule.infected);
+        }
+        let mut carrier = Carrier::new(grid.position, grid.direction);
+        carrier.bursts_causing_infection = grid.bursts_causing_infection;
+        Self {
+            rule,
+            nodes,
+            carriers: vec![carrier],
+        }
+    }
+    pub fn with_carriers(mut self, carriers: Vec<Carrier>) -> Self {
+        self.carriers = carriers;
+        self
+    }
+    pub fn bursts_causing_infection(&self) -> usize {
+        self.carriers
+            .iter()
+            .map(|carrier| carrier.bursts_causing_infection)
+            .sum()
+    }
+    pub fn steps(&mut self, count: usize) {
+        for _ in 0..count {
+            self.step();
+        }
+    }
+    fn step(&mut self) {
+        // carriers burst in order, so a later carrier on the same node sees the earlier one's change
+        for carrier in self.carriers.iter_mut() {
+            let node = self.nodes.get(&carrier.position);
+            carrier.direction = self.rule.turns[node as usize].apply(carrier.direction);
+            let next = ((node as usize + 1) % self.rule.turns.len()) as Node;
+            if next == self.rule.infected {
+                carrier.bursts_causing_infection += 1;
+            }
+            self.nodes.set(&carrier.position, next);
+            carrier.position += carrier.direction.into();
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::super::HashMapNodes;
+    use super::*;
+
+    const INPUT: &str = "..#
+#..
+...";
+
+    fn bursts(rule: &str, nodes: Nodes, steps: usize) -> usize {
+        let mut turmite = Turmite::new(rule.parse().unwrap(), INPUT.parse().unwrap(), nodes);
+        turmite.steps(steps);
+        turmite.bursts_causing_infection()
+    }
+
+    #[test]
+    fn test_part1() {
+        assert_eq!(5, bursts(PART1_RULE, HashMapNodes::default().into(), 7));
+        assert_eq!(41, bursts(PART1_RULE, HashMapNodes::default().into(), 70));
+        assert_eq!(5587, bursts(PART1_RULE, DenseNodes::new().into(), 10_000));
+    }
+
+    #[test]
+    fn test_part2() {
+        assert_eq!(26, bursts(PART2_RULE, HashMapNodes::default().into(), 100));
+        assert_eq!(
+            2511944,
+            bursts(PART2_RULE, DenseNodes::new().into(), 10_000_000)
+        );
+    }
+
+    #[test]
+    fn test_rules() {
+        assert_eq!(
+            Rule {
+                turns: vec![Turn::Left, Turn::Straight, Turn::Right, Turn::Back],
+                infected: 2
+            },
+            PART2_RULE.parse().unwrap()
+        );
+        assert!("L".parse::<Rule>().is_err());
+        assert!("LX".parse::<Rule>().is_err());
+        assert!("LR:2".parse::<Rule>().is_err());
+        assert!("LR:0".parse::<Rule>().is_err());
+
+        let input = std::fs::read_to_string("input/input22.txt").unwrap();
+        let mut grid: Grid = input.parse().unwrap();
+        grid.steps(10_000);
+        let mut turmite = Turmite::new(
+            PART1_RULE.parse().unwrap(),
+            input.parse().unwrap(),
+            DenseNodes::new().into(),
+        );
+        turmite.steps(10_000);
+        assert_eq!(
+            grid.bursts_causing_infection,
+            turmite.bursts_causing_infection()
+        );
+    }
+
+    #[test]
+    fn test_multiple_carriers() {
+        let rule: Rule = PART1_RULE.parse().unwrap();
+        let grid = || Grid::new(Default::default());
+
+        let mut turmite = Turmite::new(rule.clone(), grid(), DenseNodes::new().into())
+            .with_carriers(vec![Carrier::new(Coord::new(0, 0), Direction::Up); 2]);
+        turmite.steps(1);
+        assert_eq!(1, turmite.carriers[0].bursts_causing_infection);
+        assert_eq!(0, turmite.carriers[1].bursts_causing_infection);
+        assert_eq!(Coord::new(-1, 0), turmite.carriers[0].position);
+        assert_eq!(Coord::new(1, 0), turmite.carriers[1].position);
+
+        let far_apart = vec![
+            Carrier::new(Coord::new(-10_000, 0), Direction::Up),
+            Carrier::new(Coord::new(10_000, 0), Direction::Down),
+        ];
+        let mut turmite = Turmite::new(rule.clone(), grid(), HashMapNodes::default().into())
+            .with_carriers(far_apart);
+        turmite.steps(1_000);
+        let mut single = Turmite::new(rule, grid(), HashMapNodes::default().into());
+        single.steps(1_000);
+        for carrier in turmite.carriers.iter() {
+            assert_eq!(
+                single.bursts_causing_infection(),
+                carrier.bursts_causing_infection
+            );
+        }
+        assert_eq!(
+            "3,-4,L".parse::<Carrier>().unwrap().position,
+            Coord::new(3, -4)
+        );
+        assert!("3,-4".parse::<Carrier>().is_err());
+    }
+}
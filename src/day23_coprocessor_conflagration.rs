@@ -4,6 +4,8 @@ use std::str::FromStr;
 
 use log::debug;
 
+use crate::primes::is_prime;
+
 pub(crate) fn run() {
     let input = std::fs::read_to_string("input/input23mod.txt").unwrap();
     let instructions: Vec<Instruction> = input.lines().map(|l| l.parse().unwrap()).collect();
@@ -137,8 +139,14 @@ impl<'a, 'b> Cpu<'a, 'b> {
             }
             Instruction::SetIsPrime(r, val) => {
                 let val = val.get(&self.registers);
-                self.registers
-                    .insert(*r, if is_prime(val as usize) { 1 } else { 0 });
+                self.registers.insert(
+                    *r,
+                    if val >= 0 && is_prime(val as u64) {
+                        1
+                    } else {
+                        0
+                    },
+                );
                 self.debugger.times_mul_invoked += (val - 2).pow(2) as u64;
             }
             Instruction::NoOperation => {}
@@ -152,31 +160,6 @@ impl<'a, 'b> Cpu<'a, 'b> {
     }
 }
 
-fn is_prime(n: usize) -> bool {
-    let mut not_prime = vec![false; n + 1];
-    let mut last = 1;
-    while last < n {
-        let p = if let Some(p) = not_prime
-            .iter()
-            .enumerate()
-            .skip(last + 1)
-            .filter(|(_, &marked)| !marked)
-            .map(|(p, _)| p)
-            .next()
-        {
-            p
-        } else {
-            break;
-        };
-        for i in 2..=n / p {
-            not_prime[i * p] = true;
-        }
-        last = p;
-    }
-
-    !not_prime[n]
-}
-
 #[derive(Debug)]
 enum RegisterOrValue {
     Register(char),
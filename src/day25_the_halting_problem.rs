@@ -1,147 +1,377 @@
+use std::collections::hash_map::Entry;
 use std::collections::HashMap;
+use std::fmt::{Display, Formatter};
+use std::iter::Peekable;
 use std::str::FromStr;
 
 use anyhow::{bail, Context};
+use enum_dispatch::enum_dispatch;
 use lazy_static::lazy_static;
 use log::debug;
 use regex::Regex;
+use serde::{Deserialize, Serialize};
+
+use run_length_tape::RunLengthTape;
+use validate::SourceLines;
+
+mod analysis;
+mod compiled;
+mod format;
+mod macro_machine;
+mod render;
+mod run_length_tape;
+mod validate;
 
 pub(crate) fn run() {
-    let input = std::fs::read_to_string("input/input25.txt").unwrap();
-    let mut machine: Machine = input.parse().unwrap();
-    println!("diagnostic checksum: {}", machine.run_until_checksum());
+    let args: Vec<String> = std::env::args().skip(2).collect();
+    match args.first().map(|arg| arg.as_str()) {
+        Some("classify") => analysis::run_classify(&args[1..]),
+        Some("busy-beaver") => analysis::run_busy_beaver(&args[1..]),
+        Some("trace") => render::run_trace(&args[1..]),
+        Some("diagram") => render::run_diagram(&args[1..]),
+        Some("convert") => format::run_convert(&args[1..]),
+        Some("macro") => {
+            let input = std::fs::read_to_string("input/input25.txt").unwrap();
+            let mut machine: Machine = input.parse().unwrap();
+            if let Some(steps) = args.get(2) {
+                machine.checksum_after = steps.parse().expect("steps should be an integer");
+            }
+            let block_size = args
+                .get(1)
+                .map(|arg| arg.parse().expect("block size should be an integer"))
+                .unwrap_or(64);
+            let mut macro_machine = macro_machine::MacroMachine::new(&machine, block_size).unwrap();
+            println!(
+                "diagnostic checksum after {} steps: {}",
+                machine.checksum_after,
+                macro_machine.run_until_checksum().unwrap()
+            );
+        }
+        Some("run-length") => {
+            let input = std::fs::read_to_string("input/input25.txt").unwrap();
+            let machine: Machine = input.parse().unwrap();
+            let mut machine = machine.with_tapes(|| RunLengthTape::new().into());
+            println!("diagnostic checksum: {}", machine.run_until_checksum());
+        }
+        Some("interpret") => {
+            let input = std::fs::read_to_string("input/input25.txt").unwrap();
+            let mut machine: Machine = input.parse().unwrap();
+            println!("diagnostic checksum: {}", machine.run_until_checksum());
+        }
+        _ => {
+            let input = std::fs::read_to_string("input/input25.txt").unwrap();
+            let machine: Machine = input.parse().unwrap();
+            let mut compiled = compiled::CompiledMachine::new(&machine).unwrap();
+            println!(
+                "diagnostic checksum: {}",
+                compiled.run_until_checksum().unwrap()
+            );
+        }
+    }
 }
 
+type Symbol = u8;
+
+#[derive(Clone)]
 struct Machine {
     state: char,
-    tape: Tape,
+    tapes: Vec<Tape>,
     states: HashMap<char, State>,
     checksum_after: u64,
+    halt_state: Option<char>,
 }
 
 impl Machine {
+    pub fn new(
+        state: char,
+        states: HashMap<char, State>,
+        checksum_after: u64,
+    ) -> anyhow::Result<Self> {
+        let mut tape_count = None;
+        for (c, state) in states.iter() {
+            for (condition, action) in state.conditions.iter() {
+                let count = *tape_count.get_or_insert(condition.0.len());
+                if condition.0.len() != count
+                    || action.write_values.len() != count
+                    || action.move_to.len() != count
+                {
+                    bail!("state {} does not use {} tapes consistently", c, count);
+                }
+            }
+        }
+        Ok(Self {
+            state,
+            tapes: (0..tape_count.unwrap_or(1)).map(|_| Tape::new()).collect(),
+            states,
+            checksum_after,
+            halt_state: None,
+        })
+    }
+    pub fn with_halt_state(mut self, halt_state: char) -> Self {
+        self.halt_state = Some(halt_state);
+        self
+    }
+    pub fn with_tapes(mut self, new_tape: impl Fn() -> Tape) -> Self {
+        self.tapes = self.tapes.iter().map(|_| new_tape()).collect();
+        self
+    }
     pub fn run_until_checksum(&mut self) -> usize {
         for step in 0..self.checksum_after {
-            let state = self
-                .states
-                .get(&self.state)
-                .with_context(|| format!("cannot find state '{}' at step {}", self.state, step))
+            if self.is_halted() {
+                break;
+            }
+            self.step()
+                .with_context(|| format!("at step {}", step))
                 .unwrap();
-            self.state = state.execute(&mut self.tape);
         }
-        self.tape.count_set()
+        self.checksum()
+    }
+    pub fn checksum(&self) -> usize {
+        self.tapes.iter().map(|tape| tape.count_set()).sum()
+    }
+    pub fn max_symbol(&self) -> Symbol {
+        self.states
+            .values()
+            .flat_map(|state| state.conditions.iter())
+            .flat_map(|(condition, action)| condition.0.iter().chain(action.write_values.iter()))
+            .copied()
+            .max()
+            .unwrap_or_default()
+    }
+    pub fn is_halted(&self) -> bool {
+        self.halt_state == Some(self.state)
+    }
+    pub fn next_action(&self) -> Option<&StateAction> {
+        self.states.get(&self.state)?.action_for(&self.tapes)
+    }
+    pub fn step(&mut self) -> anyhow::Result<()> {
+        let state = self
+            .states
+            .get(&self.state)
+            .with_context(|| format!("cannot find state '{}'", self.state))?;
+        self.state = state.execute(&mut self.tapes)?;
+        Ok(())
+    }
+}
+
+impl Display for Machine {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        fn join<T: ToString>(values: &[T]) -> String {
+            values
+                .iter()
+                .map(|value| value.to_string())
+                .collect::<Vec<_>>()
+                .join(", ")
+        }
+        let multiple = self.tapes.len() > 1;
+        write!(
+            f,
+            "Begin in state {}.\nPerform a diagnostic checksum after {} steps.",
+            self.state, self.checksum_after
+        )?;
+        if let Some(halt_state) = self.halt_state {
+            write!(f, "\nHalt in state {}.", halt_state)?;
+        }
+        let mut names: Vec<_> = self.states.keys().collect();
+        names.sort();
+        for name in names {
+            write!(f, "\n\nIn state {}:", name)?;
+            for (condition, action) in self.states[name].conditions.iter() {
+                if multiple {
+                    write!(f, "\n  If the current values are {}:", join(&condition.0))?;
+                    write!(
+                        f,
+                        "\n    - Write the values {}.",
+                        join(&action.write_values)
+                    )?;
+                    write!(
+                        f,
+                        "\n    - Move the slots to the {}.",
+                        join(&action.move_to)
+                    )?;
+                } else {
+                    write!(f, "\n  If the current value is {}:", condition.0[0])?;
+                    write!(f, "\n    - Write the value {}.", action.write_values[0])?;
+                    write!(f, "\n    - Move one slot to the {}.", action.move_to[0])?;
+                }
+                write!(f, "\n    - Continue with state {}.", action.continue_with)?;
+            }
+        }
+        Ok(())
     }
 }
 
-struct Tape {
-    cursor: i32,
-    vec_right: Vec<bool>,
-    vec_left: Vec<bool>,
+#[enum_dispatch]
+trait TapeBackend {
+    fn cursor(&self) -> i32;
+    fn move_to(&mut self, direction: Direction);
+    fn get(&self) -> Symbol;
+    fn set(&mut self, value: Symbol);
+    fn count_set(&self) -> usize;
+    fn contents(&self) -> (i32, Vec<Symbol>);
+}
+
+#[enum_dispatch(TapeBackend)]
+#[derive(Clone)]
+enum Tape {
+    VecTape,
+    RunLengthTape,
 }
 
 impl Tape {
+    pub fn new() -> Self {
+        VecTape::new().into()
+    }
+}
+
+#[derive(Clone)]
+struct VecTape {
+    cursor: i32,
+    vec_right: Vec<Symbol>,
+    vec_left: Vec<Symbol>,
+}
+
+impl VecTape {
     pub fn new() -> Self {
         Self {
             cursor: 0,
-            vec_right: vec![false],
+            vec_right: vec![0],
             vec_left: vec![],
         }
     }
     fn move_left(&mut self) {
         self.cursor -= 1;
         if self.cursor < -(self.vec_left.len() as i32) {
-            self.vec_left.push(false);
+            self.vec_left.push(0);
         }
     }
     fn move_right(&mut self) {
         self.cursor += 1;
         if self.cursor >= self.vec_right.len() as i32 {
-            self.vec_right.push(false);
+            self.vec_right.push(0);
         }
     }
-    pub fn move_to(&mut self, direction: Direction) {
+    fn get_mut(&mut self) -> &mut Symbol {
+        if self.cursor < 0 {
+            &mut self.vec_left[(self.cursor.abs() - 1) as usize]
+        } else {
+            &mut self.vec_right[self.cursor as usize]
+        }
+    }
+}
+
+impl TapeBackend for VecTape {
+    fn cursor(&self) -> i32 {
+        self.cursor
+    }
+    fn move_to(&mut self, direction: Direction) {
         match direction {
             Direction::Right => self.move_right(),
             Direction::Left => self.move_left(),
         }
     }
-    pub fn get(&self) -> bool {
+    fn get(&self) -> Symbol {
         if self.cursor < 0 {
             self.vec_left[(self.cursor.abs() - 1) as usize]
         } else {
             self.vec_right[self.cursor as usize]
         }
     }
-    pub fn get_mut(&mut self) -> &mut bool {
-        if self.cursor < 0 {
-            &mut self.vec_left[(self.cursor.abs() - 1) as usize]
-        } else {
-            &mut self.vec_right[self.cursor as usize]
-        }
+    fn set(&mut self, value: Symbol) {
+        *self.get_mut() = value;
     }
-    pub fn count_set(&self) -> usize {
+    fn count_set(&self) -> usize {
         self.vec_right
             .iter()
             .chain(self.vec_left.iter())
-            .filter(|val| **val)
+            .filter(|val| **val != 0)
             .count()
     }
+    fn contents(&self) -> (i32, Vec<Symbol>) {
+        (
+            -(self.vec_left.len() as i32),
+            self.vec_left
+                .iter()
+                .rev()
+                .chain(self.vec_right.iter())
+                .copied()
+                .collect(),
+        )
+    }
 }
 
-#[derive(Debug)]
+#[derive(Debug, Clone)]
 struct State {
-    conditions: [(Condition, StateAction); 2],
+    conditions: Vec<(Condition, StateAction)>,
 }
 
 impl State {
-    pub fn execute(&self, tape: &mut Tape) -> char {
-        let action = self
-            .conditions
+    pub fn action_for(&self, tapes: &[Tape]) -> Option<&StateAction> {
+        self.conditions
             .iter()
-            .filter(|(condition, _)| condition.evaluate(tape))
+            .find(|(condition, _)| condition.evaluate(tapes))
             .map(|(_, action)| action)
-            .next()
-            .context("no matching condition")
-            .unwrap();
-        *tape.get_mut() = action.write_value;
-        tape.move_to(action.move_to);
+    }
+    pub fn execute(&self, tapes: &mut [Tape]) -> anyhow::Result<char> {
+        let action = self.action_for(tapes).context("no matching condition")?;
+        for ((tape, &value), &direction) in tapes
+            .iter_mut()
+            .zip(action.write_values.iter())
+            .zip(action.move_to.iter())
+        {
+            tape.set(value);
+            tape.move_to(direction);
+        }
 
-        action.continue_with
+        Ok(action.continue_with)
     }
 }
 
-#[derive(Debug)]
-struct Condition(bool);
+#[derive(Debug, Clone)]
+struct Condition(Vec<Symbol>);
 
 impl Condition {
-    pub fn evaluate(&self, tape: &Tape) -> bool {
-        self.0 == tape.get()
+    pub fn evaluate(&self, tapes: &[Tape]) -> bool {
+        self.0
+            .iter()
+            .zip(tapes.iter())
+            .all(|(&value, tape)| value == tape.get())
     }
 }
 
-#[derive(Debug)]
+#[derive(Debug, Clone)]
 struct StateAction {
-    write_value: bool,
-    move_to: Direction,
+    write_values: Vec<Symbol>,
+    move_to: Vec<Direction>,
     continue_with: char,
 }
 
 impl StateAction {
-    pub fn new(write_value: bool, move_to: Direction, continue_with: char) -> Self {
+    pub fn new(write_values: Vec<Symbol>, move_to: Vec<Direction>, continue_with: char) -> Self {
         Self {
-            write_value,
+            write_values,
             move_to,
             continue_with,
         }
     }
 }
 
-#[derive(Debug, Copy, Clone)]
+#[derive(Debug, Copy, Clone, Eq, PartialEq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
 enum Direction {
     Right,
     Left,
 }
 
+impl Display for Direction {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Direction::Right => write!(f, "right"),
+            Direction::Left => write!(f, "left"),
+        }
+    }
+}
+
 impl FromStr for Direction {
     type Err = anyhow::Error;
 
@@ -159,115 +389,172 @@ impl FromStr for Condition {
 
     fn from_str(s: &str) -> Result<Self, Self::Err> {
         lazy_static! {
-            static ref CONDITION: Regex = Regex::new(r#"If the current value is (\d+):"#).unwrap();
+            static ref CONDITION: Regex =
+                Regex::new(r#"If the current values? (?:is|are) (\d+(?:, \d+)*):"#).unwrap();
         }
-        let value = CONDITION
+        let values = CONDITION
             .captures_iter(s)
             .next()
             .with_context(|| format!("invalid condition '{}'", s))?
             .get(1)
             .context("get condition val")?
             .as_str();
-        Ok(Condition(parse_int_to_bool(value)?))
+        Ok(Condition(parse_symbols(values)?))
     }
 }
 
-fn parse_int_to_bool(s: &str) -> anyhow::Result<bool> {
-    Ok(match s.parse::<i32>()? {
-        0 => false,
-        1 => true,
-        other => bail!("invalid bool '{}'", other),
-    })
+fn parse_symbols(s: &str) -> anyhow::Result<Vec<Symbol>> {
+    s.split(", ")
+        .map(|value| {
+            value
+                .parse::<Symbol>()
+                .with_context(|| format!("invalid symbol '{}'", value))
+        })
+        .collect()
 }
 
 impl FromStr for Machine {
     type Err = anyhow::Error;
 
     fn from_str(s: &str) -> Result<Self, Self::Err> {
-        lazy_static! {
-            static ref BEGIN: Regex = Regex::new(r#"Begin in state ([A-Z])."#).unwrap();
-            static ref CHECK: Regex =
-                Regex::new(r#"Perform a diagnostic checksum after (\d+) steps."#).unwrap();
-        }
-        let mut lines = s.lines();
-        let state = BEGIN
-            .captures_iter(lines.next().context("machine line 1 not found")?)
-            .next()
-            .context("invalid machine line 1")?
-            .get(1)
-            .unwrap()
-            .as_str()
-            .chars()
-            .next()
-            .unwrap();
-        let checksum_after: u64 = CHECK
-            .captures_iter(lines.next().context("machine line 2 not found")?)
-            .next()
-            .context("invalid machine line 2")?
-            .get(1)
-            .unwrap()
-            .as_str()
-            .parse()?;
+        let (machine, source_lines) = parse_machine(s)?;
+        validate::check(&machine, &source_lines)?;
+        Ok(machine)
+    }
+}
 
-        let mut states = HashMap::new();
-        while let Some((c, state)) = try_parse_state(&mut lines)? {
-            debug!("got state {}: {:?}", c, state);
-            states.insert(c, state);
-        }
+fn parse_machine(s: &str) -> anyhow::Result<(Machine, SourceLines)> {
+    lazy_static! {
+        static ref BEGIN: Regex = Regex::new(r#"Begin in state ([A-Z])."#).unwrap();
+        static ref CHECK: Regex =
+            Regex::new(r#"Perform a diagnostic checksum after (\d+) steps."#).unwrap();
+        static ref HALT: Regex = Regex::new(r#"Halt in state ([A-Z])."#).unwrap();
+    }
+    let mut lines = s
+        .lines()
+        .enumerate()
+        .map(|(i, line)| (i + 1, line))
+        .peekable();
+    let state = BEGIN
+        .captures_iter(lines.next().context("machine line 1 not found")?.1)
+        .next()
+        .context("invalid machine line 1")?
+        .get(1)
+        .unwrap()
+        .as_str()
+        .chars()
+        .next()
+        .unwrap();
+    let checksum_after: u64 = CHECK
+        .captures_iter(lines.next().context("machine line 2 not found")?.1)
+        .next()
+        .context("invalid machine line 2")?
+        .get(1)
+        .unwrap()
+        .as_str()
+        .parse()?;
+    let halt_state = lines
+        .peek()
+        .and_then(|(_, line)| HALT.captures(line))
+        .map(|captures| captures[1].chars().next().unwrap());
+    if halt_state.is_some() {
+        lines.next();
+    }
 
-        Ok(Self {
-            state,
-            tape: Tape::new(),
-            states,
-            checksum_after,
-        })
+    let mut states = HashMap::new();
+    let mut source_lines = SourceLines::default();
+    while let Some((c, state)) = try_parse_state(&mut lines, &mut source_lines)? {
+        debug!("got state {}: {:?}", c, state);
+        states.entry(c).or_insert(state);
     }
+
+    let machine = Machine::new(state, states, checksum_after)?;
+    Ok((
+        match halt_state {
+            Some(halt_state) => machine.with_halt_state(halt_state),
+            None => machine,
+        },
+        source_lines,
+    ))
 }
 
 fn try_parse_state<'a>(
-    lines: &mut impl Iterator<Item = &'a str>,
+    lines: &mut Peekable<impl Iterator<Item = (usize, &'a str)>>,
+    source_lines: &mut SourceLines,
 ) -> anyhow::Result<Option<(char, State)>> {
     if lines.next().is_none() {
         return Ok(None);
     }
     lazy_static! {
         static ref STATE: Regex = Regex::new(r#"In state ([A-Z]):"#).unwrap();
+        static ref WRITE: Regex = Regex::new(r#"Write the values? (\d+(?:, \d+)*)\."#).unwrap();
+        static ref MOVE: Regex = Regex::new(r#"\b(left|right)\b"#).unwrap();
+        static ref CONTINUE: Regex = Regex::new(r#"Continue with state ([A-Z])\."#).unwrap();
     }
-    let line = lines.next().context("missing state line")?;
+    let (line_number, line) = lines.next().context("missing state line")?;
     let state_name = STATE
         .captures_iter(line)
         .next()
-        .with_context(|| format!("invalid state line 1: '{}'", line))?
+        .with_context(|| format!("line {}: invalid state line: '{}'", line_number, line))?
         .get(1)
         .unwrap()
         .as_str()
         .chars()
         .next()
         .unwrap();
-    fn get_action_value(s: &str) -> anyhow::Result<&str> {
-        Ok(s.split_whitespace()
-            .last()
-            .context("invalid action")?
-            .trim_end_matches('.'))
-    }
-    let conditions = (0..2)
-        .map(|_| {
-            let condition: Condition = lines.next().context("condition not found")?.parse()?;
-            let actions = StateAction::new(
-                parse_int_to_bool(get_action_value(
-                    lines.next().context("not enough actions")?,
-                )?)?,
-                get_action_value(lines.next().context("not enough actions")?)?.parse()?,
-                get_action_value(lines.next().context("not enough actions")?)?
-                    .chars()
-                    .next()
-                    .context("invalid state")?,
-            );
-            Ok((condition, actions))
-        })
-        .collect::<anyhow::Result<Vec<(Condition, StateAction)>>>()?
-        .try_into()
-        .unwrap();
+    fn capture<'a>(regex: &Regex, s: Option<(usize, &'a str)>) -> anyhow::Result<&'a str> {
+        let (line_number, s) = s.context("not enough actions")?;
+        Ok(regex
+            .captures(s)
+            .with_context(|| format!("line {}: invalid action '{}'", line_number, s))?
+            .get(1)
+            .unwrap()
+            .as_str())
+    }
+    let mut conditions = Vec::new();
+    let mut condition_lines = Vec::new();
+    while lines
+        .peek()
+        .map(|(_, line)| line.trim_start().starts_with("If"))
+        .unwrap_or(false)
+    {
+        let (condition_line, line) = lines.next().unwrap();
+        let condition: Condition = line
+            .parse()
+            .with_context(|| format!("line {}", condition_line))?;
+        let write_values = parse_symbols(capture(&WRITE, lines.next())?)?;
+        let (_, line) = lines.next().context("not enough actions")?;
+        let move_to = MOVE
+            .find_iter(line)
+            .map(|direction| direction.as_str().parse())
+            .collect::<anyhow::Result<Vec<Direction>>>()?;
+        let continue_line = lines.peek().map(|(i, _)| *i).unwrap_or_default();
+        let continue_with = capture(&CONTINUE, lines.next())?
+            .chars()
+            .next()
+            .context("invalid state")?;
+        conditions.push((
+            condition,
+            StateAction::new(write_values, move_to, continue_with),
+        ));
+        condition_lines.push((condition_line, continue_line));
+    }
+    if conditions.is_empty() {
+        bail!(
+            "line {}: state {} has no conditions",
+            line_number,
+            state_name
+        );
+    }
+    match source_lines.states.entry(state_name) {
+        Entry::Occupied(_) => source_lines
+            .duplicate_states
+            .push((state_name, line_number)),
+        Entry::Vacant(entry) => {
+            entry.insert(line_number);
+            source_lines.conditions.insert(state_name, condition_lines);
+        }
+    }
     Ok(Some((state_name, State { conditions })))
 }
 
@@ -275,6 +562,38 @@ fn try_parse_state<'a>(
 mod tests {
     use super::*;
 
+    pub(super) const BUSY_BEAVER_2_STATES_3_SYMBOLS: &str = "Begin in state A.
+Perform a diagnostic checksum after 38 steps.
+Halt in state Z.
+
+In state A:
+  If the current value is 0:
+    - Write the value 1.
+    - Move one slot to the right.
+    - Continue with state B.
+  If the current value is 1:
+    - Write the value 2.
+    - Move one slot to the left.
+    - Continue with state B.
+  If the current value is 2:
+    - Write the value 1.
+    - Move one slot to the left.
+    - Continue with state Z.
+
+In state B:
+  If the current value is 0:
+    - Write the value 2.
+    - Move one slot to the left.
+    - Continue with state A.
+  If the current value is 1:
+    - Write the value 2.
+    - Move one slot to the right.
+    - Continue with state B.
+  If the current value is 2:
+    - Write the value 1.
+    - Move one slot to the left.
+    - Continue with state B.";
+
     #[test]
     fn test1() {
         let input = "Begin in state A.
@@ -303,4 +622,67 @@ In state B:
         let mut machine: Machine = input.parse().unwrap();
         assert_eq!(3, machine.run_until_checksum());
     }
+    #[test]
+    fn test_multiple_symbols() {
+        let mut machine: Machine = BUSY_BEAVER_2_STATES_3_SYMBOLS.parse().unwrap();
+        assert_eq!(9, machine.run_until_checksum());
+        assert_eq!('Z', machine.state);
+    }
+
+    #[test]
+    fn test_multiple_tapes() {
+        let input = "Begin in state A.
+Perform a diagnostic checksum after 7 steps.
+
+In state A:
+  If the current values are 0, 0:
+    - Write the values 1, 2.
+    - Move the slots to the right, left.
+    - Continue with state B.
+  If the current values are 1, 0:
+    - Write the values 0, 0.
+    - Move the slots to the left, right.
+    - Continue with state A.
+  If the current values are 0, 1:
+    - Write the values 1, 1.
+    - Move the slots to the right, right.
+    - Continue with state B.
+  If the current values are 0, 2:
+    - Write the values 2, 0.
+    - Move the slots to the left, right.
+    - Continue with state A.
+
+In state B:
+  If the current values are 0, 0:
+    - Write the values 0, 1.
+    - Move the slots to the left, left.
+    - Continue with state A.
+  If the current values are 0, 1:
+    - Write the values 2, 1.
+    - Move the slots to the right, right.
+    - Continue with state A.
+  If the current values are 0, 2:
+    - Write the values 1, 2.
+    - Move the slots to the right, left.
+    - Continue with state B.";
+
+        let mut machine: Machine = input.parse().unwrap();
+        assert_eq!(5, machine.run_until_checksum());
+        assert_eq!(4, machine.tapes[0].count_set());
+        assert_eq!(1, machine.tapes[1].count_set());
+    }
+
+    #[test]
+    fn test_inconsistent_tapes() {
+        let input = "Begin in state A.
+Perform a diagnostic checksum after 1 steps.
+
+In state A:
+  If the current values are 0, 0:
+    - Write the value 1.
+    - Move the slots to the right, left.
+    - Continue with state A.";
+
+        assert!(input.parse::<Machine>().is_err());
+    }
 }
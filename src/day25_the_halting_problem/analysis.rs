@@ -0,0 +1,423 @@
+use std::collections::HashMap;
+
+use anyhow::{bail, Context};
+
+use super::{format, Condition, Direction, Machine, State, StateAction, Symbol, Tape, TapeBackend};
+
+pub(super) fn run_classify(args: &[String]) {
+    const USAGE: &str = "usage: classify <file> [halt state] [max steps]";
+    let path = args.first().expect(USAGE);
+    let input = std::fs::read_to_string(path).unwrap();
+    let mut machine = format::parse_any(&input).unwrap();
+    if let Some(halt_state) = args.get(1) {
+        machine = machine.with_halt_state(halt_state.chars().next().expect(USAGE));
+    }
+    let max_steps = args
+        .get(2)
+        .map(|arg| arg.parse().expect("max steps should be an integer"))
+        .unwrap_or(10_000);
+    println!(
+        "behaviour: {:?}",
+        classify(&mut machine, max_steps).unwrap()
+    );
+    println!("non-blank cells: {}", machine.checksum());
+}
+
+pub(super) fn run_busy_beaver(args: &[String]) {
+    let mut args = args
+        .iter()
+        .map(|arg| arg.parse::<u64>().expect("arguments should be integers"));
+    let states = args.next().unwrap_or(2) as usize;
+    let symbols = args.next().unwrap_or(2) as Symbol;
+    let step_limit = args.next().unwrap_or(1_000);
+    let report = busy_beaver(states, symbols, step_limit);
+    println!(
+        "busy beaver for {} states and {} symbols (step limit {}):",
+        states, symbols, step_limit
+    );
+    println!("max steps: {}", report.max_steps);
+    println!("max non-blank cells: {}", report.max_non_blank);
+    println!(
+        "machines enumerated: {} (halted: {}, cyclers: {}, translated cyclers: {}, unknown: {})",
+        report.enumerated, report.halted, report.cyclers, report.translated_cyclers, report.unknown
+    );
+}
+
+#[derive(Debug, Eq, PartialEq)]
+pub(super) enum Behaviour {
+    Halted { steps: u64 },
+    Cycler { start: u64, period: u64 },
+    TranslatedCycler { start: u64, period: u64, shift: i32 },
+    Unknown,
+}
+
+struct Record {
+    step: u64,
+    state: char,
+    position: i32,
+    side: Direction,
+    tape: Snapshot,
+}
+
+#[derive(Clone, Hash, Eq, PartialEq)]
+struct Snapshot {
+    offset: i32,
+    values: Vec<Symbol>,
+}
+
+impl Snapshot {
+    pub fn trimmed(tape: &Tape) -> Self {
+        let (first, values) = tape.contents();
+        let start = values.iter().position(|&value| value != 0);
+        let end = values.iter().rposition(|&value| value != 0);
+        match (start, end) {
+            (Some(start), Some(end)) => Self {
+                offset: first + start as i32,
+                values: values[start..=end].to_vec(),
+            },
+            _ => Self {
+                offset: 0,
+                values: vec![],
+            },
+        }
+    }
+    pub fn get(&self, position: i32) -> Symbol {
+        let index = position - self.offset;
+        if index < 0 {
+            0
+        } else {
+            self.values.get(index as usize).copied().unwrap_or_default()
+        }
+    }
+}
+
+pub(super) fn classify(machine: &mut Machine, max_steps: u64) -> anyhow::Result<Behaviour> {
+    if machine.tapes.len() != 1 {
+        bail!("only single tape machines can be classified");
+    }
+    let mut configurations: HashMap<(char, i32, Snapshot), u64> = HashMap::new();
+    let mut records: Vec<Record> = Vec::new();
+    let mut positions: Vec<i32> = Vec::new();
+    let (mut min_visited, mut max_visited) = (0, 0);
+    for step in 0..max_steps {
+        if machine.is_halted() {
+            return Ok(Behaviour::Halted { steps: step });
+        }
+        let tape = &machine.tapes[0];
+        let position = tape.cursor();
+        positions.push(position);
+
+        let configuration = (machine.state, position, Snapshot::trimmed(tape));
+        if let Some(&start) = configurations.get(&configuration) {
+            return Ok(Behaviour::Cycler {
+                start,
+                period: step - start,
+            });
+        }
+
+        let side = if position > max_visited {
+            max_visited = position;
+            Some(Direction::Right)
+        } else if position < min_visited {
+            min_visited = position;
+            Some(Direction::Left)
+        } else {
+            None
+        };
+        if let Some(side) = side {
+            for record in records
+                .iter()
+                .filter(|record| record.side == side && record.state == machine.state)
+            {
+                let shift = position - record.position;
+                let visited = &positions[record.step as usize..];
+                let repeats = match side {
+                    Direction::Right => {
+                        let furthest = *visited.iter().min().unwrap();
+                        (furthest..record.position)
+                            .all(|i| record.tape.get(i) == configuration.2.get(i + shift))
+                    }
+                    Direction::Left => {
+                        let furthest = *visited.iter().max().unwrap();
+                        (record.position + 1..=furthest)
+                            .all(|i| record.tape.get(i) == configuration.2.get(i + shift))
+                    }
+                };
+                if repeats {
+                    return Ok(Behaviour::TranslatedCycler {
+                        start: record.step,
+                        period: step - record.step,
+                        shift,
+                    });
+                }
+            }
+            records.push(Record {
+                step,
+                state: machine.state,
+                position,
+                side,
+                tape: configuration.2.clone(),
+            });
+        }
+        configurations.insert(configuration, step);
+
+        machine
+            .step()
+            .with_context(|| format!("at step {}", step))?;
+    }
+    Ok(if machine.is_halted() {
+        Behaviour::Halted { steps: max_steps }
+    } else {
+        Behaviour::Unknown
+    })
+}
+
+#[derive(Debug, Default)]
+pub(super) struct BusyBeaverReport {
+    pub max_steps: u64,
+    pub max_non_blank: usize,
+    pub enumerated: usize,
+    pub halted: usize,
+    pub cyclers: usize,
+    pub translated_cyclers: usize,
+    pub unknown: usize,
+}
+
+pub(super) fn busy_beaver(states: usize, symbols: Symbol, step_limit: u64) -> BusyBeaverReport {
+    let machine = Machine {
+        state: 'A',
+        tapes: vec![Tape::new()],
+        states: HashMap::new(),
+        checksum_after: step_limit,
+        halt_state: None,
+    };
+    let mut enumeration = Enumeration {
+        states,
+        symbols,
+        step_limit,
+        report: Default::default(),
+    };
+    enumeration.explore(machine, 0, 1, states * symbols as usize);
+    enumeration.report
+}
+
+struct Enumeration {
+    states: usize,
+    symbols: Symbol,
+    step_limit: u64,
+    report: BusyBeaverReport,
+}
+
+impl Enumeration {
+    fn explore(
+        &mut self,
+        mut machine: Machine,
+        mut steps: u64,
+        used_states: usize,
+        undefined: usize,
+    ) {
+        while steps < self.step_limit {
+            if machine.next_action().is_none() {
+                self.add_halting(&machine, steps + 1);
+                if undefined > 1 {
+                    self.branch(&machine, steps, used_states, undefined);
+                }
+                return;
+            }
+            machine.step().unwrap();
+            steps += 1;
+        }
+        self.add_non_halting(&machine);
+    }
+
+    fn branch(&mut self, machine: &Machine, steps: u64, used_states: usize, undefined: usize) {
+        let read = machine.tapes[0].get();
+        for next in 0..self.states.min(used_states + 1) {
+            for write in 0..self.symbols {
+                for direction in [Direction::Left, Direction::Right] {
+                    if steps == 0 && direction == Direction::Left {
+                        continue;
+                    }
+                    let mut machine = machine.clone();
+                    machine
+                        .states
+                        .entry(machine.state)
+                        .or_insert(State { conditions: vec![] })
+                        .conditions
+                        .push((
+                            Condition(vec![read]),
+                            StateAction::new(
+                                vec![write],
+                                vec![direction],
+                                (b'A' + next as u8) as char,
+                            ),
+                        ));
+                    self.explore(machine, steps, used_states.max(next + 1), undefined - 1);
+                }
+            }
+        }
+    }
+
+    fn add_halting(&mut self, machine: &Machine, steps: u64) {
+        let non_blank = machine.checksum() + if machine.tapes[0].get() == 0 { 1 } else { 0 };
+        self.report.enumerated += 1;
+        self.report.halted += 1;
+        self.report.max_steps = self.report.max_steps.max(steps);
+        self.report.max_non_blank = self.report.max_non_blank.max(non_blank);
+    }
+
+    fn add_non_halting(&mut self, machine: &Machine) {
+        let mut fresh = Machine {
+            state: 'A',
+            tapes: vec![Tape::new()],
+            states: machine.states.clone(),
+            checksum_after: self.step_limit,
+            halt_state: None,
+        };
+        self.report.enumerated += 1;
+        match classify(&mut fresh, self.step_limit).unwrap() {
+            Behaviour::Cycler { .. } => self.report.cyclers += 1,
+            Behaviour::TranslatedCycler { .. } => self.report.translated_cyclers += 1,
+            Behaviour::Halted { .. } | Behaviour::Unknown => self.report.unknown += 1,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn machine(input: &str) -> Machine {
+        input.parse().unwrap()
+    }
+
+    #[test]
+    fn test_halting() {
+        let mut machine = machine(
+            "Begin in state A.
+Perform a diagnostic checksum after 100 steps.
+Halt in state H.
+
+In state A:
+  If the current value is 0:
+    - Write the value 1.
+    - Move one slot to the right.
+    - Continue with state B.
+  If the current value is 1:
+    - Write the value 1.
+    - Move one slot to the left.
+    - Continue with state B.
+
+In state B:
+  If the current value is 0:
+    - Write the value 1.
+    - Move one slot to the left.
+    - Continue with state A.
+  If the current value is 1:
+    - Write the value 1.
+    - Move one slot to the right.
+    - Continue with state H.",
+        );
+
+        assert_eq!(
+            Behaviour::Halted { steps: 6 },
+            classify(&mut machine, 100).unwrap()
+        );
+        assert_eq!(4, machine.checksum());
+    }
+
+    #[test]
+    fn test_cycler() {
+        let mut machine = machine(
+            "Begin in state A.
+Perform a diagnostic checksum after 100 steps.
+
+In state A:
+  If the current value is 0:
+    - Write the value 1.
+    - Move one slot to the right.
+    - Continue with state B.
+  If the current value is 1:
+    - Write the value 0.
+    - Move one slot to the right.
+    - Continue with state B.
+
+In state B:
+  If the current value is 0:
+    - Write the value 0.
+    - Move one slot to the left.
+    - Continue with state A.
+  If the current value is 1:
+    - Write the value 1.
+    - Move one slot to the left.
+    - Continue with state A.",
+        );
+
+        assert_eq!(
+            Behaviour::Cycler {
+                start: 0,
+                period: 4
+            },
+            classify(&mut machine, 100).unwrap()
+        );
+    }
+
+    #[test]
+    fn test_translated_cycler() {
+        let mut machine = machine(
+            "Begin in state A.
+Perform a diagnostic checksum after 100 steps.
+
+In state A:
+  If the current value is 0:
+    - Write the value 1.
+    - Move one slot to the right.
+    - Continue with state B.
+
+In state B:
+  If the current value is 0:
+    - Write the value 0.
+    - Move one slot to the right.
+    - Continue with state A.",
+        );
+
+        assert_eq!(
+            Behaviour::TranslatedCycler {
+                start: 1,
+                period: 2,
+                shift: 2
+            },
+            classify(&mut machine, 100).unwrap()
+        );
+    }
+
+    #[test]
+    fn test_busy_beaver_2_states() {
+        let report = busy_beaver(2, 2, 100);
+        assert_eq!(6, report.max_steps);
+        assert_eq!(4, report.max_non_blank);
+        assert_eq!(0, report.unknown);
+    }
+
+    #[test]
+    fn test_busy_beaver_3_states() {
+        let report = busy_beaver(3, 2, 100);
+        assert_eq!(21, report.max_steps);
+        assert_eq!(6, report.max_non_blank);
+    }
+
+    #[test]
+    fn test_busy_beaver_2_states_3_symbols() {
+        let report = busy_beaver(2, 3, 200);
+        assert_eq!(38, report.max_steps);
+        assert_eq!(9, report.max_non_blank);
+    }
+
+    #[test]
+    #[ignore]
+    fn test_busy_beaver_4_states() {
+        let report = busy_beaver(4, 2, 300);
+        assert_eq!(107, report.max_steps);
+        assert_eq!(13, report.max_non_blank);
+    }
+}
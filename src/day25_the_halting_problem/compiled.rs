@@ -0,0 +1,191 @@
+use anyhow::{bail, Context};
+
+use super::{Direction, Machine, Symbol};
+
+const HALT: usize = usize::MAX;
+const UNDEFINED: usize = usize::MAX - 1;
+
+#[derive(Debug, Copy, Clone)]
+struct Transition {
+    write: Symbol,
+    direction: Direction,
+    next: usize,
+}
+
+const UNDEFINED_TRANSITION: Transition = Transition {
+    write: 0,
+    direction: Direction::Right,
+    next: UNDEFINED,
+};
+
+pub(super) struct CompiledMachine {
+    names: Vec<char>,
+    symbols: usize,
+    table: Vec<Transition>,
+    state: usize,
+    tape: Vec<Symbol>,
+    head: usize,
+    checksum_after: u64,
+}
+
+impl CompiledMachine {
+    pub fn new(machine: &Machine) -> anyhow::Result<Self> {
+        if machine.tapes.len() != 1 {
+            bail!("only single tape machines can be compiled");
+        }
+        let mut names: Vec<char> = machine.states.keys().copied().collect();
+        names.sort_unstable();
+        let index = |name: char| -> anyhow::Result<usize> {
+            if machine.halt_state == Some(name) {
+                Ok(HALT)
+            } else {
+                names
+                    .binary_search(&name)
+                    .ok()
+                    .with_context(|| format!("cannot find state '{}'", name))
+            }
+        };
+        let symbols = machine.max_symbol() as usize + 1;
+
+        let mut table = vec![UNDEFINED_TRANSITION; names.len() * symbols];
+        for (i, name) in names.iter().enumerate() {
+            for (condition, action) in machine.states[name].conditions.iter() {
+                let entry = &mut table[i * symbols + condition.0[0] as usize];
+                if entry.next == UNDEFINED {
+                    *entry = Transition {
+                        write: action.write_values[0],
+                        direction: action.move_to[0],
+                        next: index(action.continue_with)?,
+                    };
+                }
+            }
+        }
+        Ok(Self {
+            state: index(machine.state)?,
+            names,
+            symbols,
+            table,
+            tape: vec![0; 1024],
+            head: 512,
+            checksum_after: machine.checksum_after,
+        })
+    }
+
+    pub fn run_until_checksum(&mut self) -> anyhow::Result<usize> {
+        self.run(self.checksum_after)?;
+        Ok(self.checksum())
+    }
+
+    pub fn run(&mut self, steps: u64) -> anyhow::Result<u64> {
+        for step in 0..steps {
+            if self.state == HALT {
+                return Ok(step);
+            }
+            let transition = self.table[self.state * self.symbols + self.tape[self.head] as usize];
+            if transition.next == UNDEFINED {
+                bail!(
+                    "no matching condition for state '{}' at step {}",
+                    self.names[self.state],
+                    step
+                );
+            }
+            self.tape[self.head] = transition.write;
+            match transition.direction {
+                Direction::Right => {
+                    self.head += 1;
+                    if self.head == self.tape.len() {
+                        self.tape.resize(self.tape.len() * 2, 0);
+                    }
+                }
+                Direction::Left => {
+                    if self.head == 0 {
+                        let mut tape = vec![0; self.tape.len()];
+                        self.head = tape.len();
+                        tape.append(&mut self.tape);
+                        self.tape = tape;
+                    }
+                    self.head -= 1;
+                }
+            }
+            self.state = transition.next;
+        }
+        Ok(steps)
+    }
+
+    pub fn checksum(&self) -> usize {
+        self.tape.iter().filter(|&&value| value != 0).count()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use utils::timer::Timer;
+
+    use super::super::RunLengthTape;
+    use super::*;
+
+    #[test]
+    fn test_input() {
+        let input = std::fs::read_to_string("input/input25.txt").unwrap();
+        let machine: Machine = input.parse().unwrap();
+        for steps in [0, 1, 10, 1_000, 100_000] {
+            let mut machine = machine.clone();
+            machine.checksum_after = steps;
+            let mut compiled = CompiledMachine::new(&machine).unwrap();
+            assert_eq!(
+                machine.run_until_checksum(),
+                compiled.run_until_checksum().unwrap()
+            );
+        }
+        let mut compiled = CompiledMachine::new(&machine).unwrap();
+        assert_eq!(633, compiled.run_until_checksum().unwrap());
+    }
+
+    #[test]
+    fn test_halting() {
+        let machine = super::super::format::from_table("1RB2LB1LZ_2LA2RB1LB").unwrap();
+        let mut compiled = CompiledMachine::new(&machine).unwrap();
+        assert_eq!(38, compiled.run(1_000).unwrap());
+        assert_eq!(HALT, compiled.state);
+        assert_eq!(9, compiled.checksum());
+    }
+
+    #[test]
+    fn test_undefined() {
+        let machine = super::super::format::from_table("1RB---_0LA1RA").unwrap();
+        let mut compiled = CompiledMachine::new(&machine).unwrap();
+        assert!(compiled.run(100).is_err());
+    }
+
+    #[test]
+    #[ignore]
+    fn benchmark() {
+        let input = std::fs::read_to_string("input/input25.txt").unwrap();
+        let machine: Machine = input.parse().unwrap();
+        let expected = {
+            let _timer =
+                Timer::start(|elapsed| println!("machine took {} ms.", elapsed.as_millis()));
+            machine.clone().run_until_checksum()
+        };
+        let run_length = {
+            let _timer = Timer::start(|elapsed| {
+                println!("run length tape took {} ms.", elapsed.as_millis())
+            });
+            machine
+                .clone()
+                .with_tapes(|| RunLengthTape::new().into())
+                .run_until_checksum()
+        };
+        let compiled = {
+            let _timer = Timer::start(|elapsed| {
+                println!("compiled machine took {} ms.", elapsed.as_millis())
+            });
+            CompiledMachine::new(&machine)
+                .unwrap()
+                .run_until_checksum()
+                .unwrap()
+        };
+        assert_eq!(expected, run_length);
+        assert_eq!(expected, compiled);
+    }
+}
@@ -0,0 +1,314 @@
+use std::collections::{BTreeMap, HashMap, HashSet};
+
+use anyhow::{bail, Context};
+use serde::{Deserialize, Serialize};
+
+use super::validate::{self, SourceLines};
+use super::{Condition, Direction, Machine, State, StateAction, Symbol};
+
+pub(super) fn run_convert(args: &[String]) {
+    let path = args
+        .first()
+        .expect("usage: convert <file> [text|table|json]");
+    let input = std::fs::read_to_string(path).unwrap();
+    let machine = parse_any(&input).unwrap();
+    match args.get(1).map(|arg| arg.as_str()).unwrap_or("table") {
+        "text" => println!("{}", machine),
+        "table" => println!("{}", to_table(&machine).unwrap()),
+        "json" => println!("{}", to_json(&machine).unwrap()),
+        other => panic!("unknown format '{}'", other),
+    }
+}
+
+pub(super) fn parse_any(s: &str) -> anyhow::Result<Machine> {
+    let s = s.trim();
+    if s.starts_with('{') {
+        from_json(s)
+    } else if s.starts_with("Begin in state") {
+        s.parse()
+    } else {
+        from_table(s)
+    }
+}
+
+pub(super) fn to_table(machine: &Machine) -> anyhow::Result<String> {
+    if machine.tapes.len() != 1 {
+        bail!("only single tape machines can be written as a table");
+    }
+    if machine.state != 'A' {
+        bail!("table machines must begin in state A");
+    }
+    let mut names: Vec<char> = machine.states.keys().copied().collect();
+    names.sort_unstable();
+    if names
+        .iter()
+        .zip('A'..)
+        .any(|(&name, expected)| name != expected)
+    {
+        bail!("table machines must name their states A, B, C, ...");
+    }
+    let symbols = symbol_count(machine);
+    if symbols > 10 {
+        bail!("table machines cannot have more than 10 symbols");
+    }
+    Ok(names
+        .iter()
+        .map(|name| {
+            let state = &machine.states[name];
+            (0..symbols)
+                .map(|symbol| {
+                    state
+                        .conditions
+                        .iter()
+                        .find(|(condition, _)| condition.0[0] == symbol)
+                        .map(|(_, action)| {
+                            format!(
+                                "{}{}{}",
+                                action.write_values[0],
+                                match action.move_to[0] {
+                                    Direction::Right => 'R',
+                                    Direction::Left => 'L',
+                                },
+                                action.continue_with
+                            )
+                        })
+                        .unwrap_or_else(|| "---".to_string())
+                })
+                .collect::<String>()
+        })
+        .collect::<Vec<_>>()
+        .join("_"))
+}
+
+pub(super) fn from_table(s: &str) -> anyhow::Result<Machine> {
+    let rows: Vec<Vec<char>> = s
+        .trim()
+        .split('_')
+        .map(|row| row.chars().collect())
+        .collect();
+    let width = rows[0].len();
+    if width == 0 || !width.is_multiple_of(3) || rows.iter().any(|row| row.len() != width) {
+        bail!("invalid table '{}'", s);
+    }
+    if rows.len() > 26 {
+        bail!("table machines cannot have more than 26 states");
+    }
+    let mut states = HashMap::new();
+    for (row, name) in rows.iter().zip('A'..) {
+        let conditions = row
+            .chunks(3)
+            .enumerate()
+            .filter(|(_, transition)| transition != &['-', '-', '-'])
+            .map(|(symbol, transition)| {
+                let write = transition[0]
+                    .to_digit(10)
+                    .with_context(|| format!("invalid symbol '{}'", transition[0]))?;
+                let direction = match transition[1] {
+                    'R' => Direction::Right,
+                    'L' => Direction::Left,
+                    other => bail!("invalid direction '{}'", other),
+                };
+                if !transition[2].is_ascii_uppercase() {
+                    bail!("invalid state '{}'", transition[2]);
+                }
+                Ok((
+                    Condition(vec![symbol as Symbol]),
+                    StateAction::new(vec![write as Symbol], vec![direction], transition[2]),
+                ))
+            })
+            .collect::<anyhow::Result<Vec<_>>>()?;
+        states.insert(name, State { conditions });
+    }
+    let halt_states: HashSet<char> = states
+        .values()
+        .flat_map(|state| state.conditions.iter())
+        .map(|(_, action)| action.continue_with)
+        .filter(|next| !states.contains_key(next))
+        .collect();
+    let machine = Machine::new('A', states, 0)?;
+    let machine = if halt_states.len() == 1 {
+        machine.with_halt_state(*halt_states.iter().next().unwrap())
+    } else {
+        machine
+    };
+    validate::check(&machine, &SourceLines::unknown(&machine))?;
+    Ok(machine)
+}
+
+#[derive(Serialize, Deserialize)]
+struct MachineDefinition {
+    start: char,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    halt: Option<char>,
+    steps: u64,
+    states: BTreeMap<char, Vec<TransitionDefinition>>,
+}
+
+#[derive(Serialize, Deserialize)]
+struct TransitionDefinition {
+    read: Vec<Symbol>,
+    write: Vec<Symbol>,
+    #[serde(rename = "move")]
+    move_to: Vec<Direction>,
+    next: char,
+}
+
+pub(super) fn to_json(machine: &Machine) -> anyhow::Result<String> {
+    let definition = MachineDefinition {
+        start: machine.state,
+        halt: machine.halt_state,
+        steps: machine.checksum_after,
+        states: machine
+            .states
+            .iter()
+            .map(|(&name, state)| {
+                (
+                    name,
+                    state
+                        .conditions
+                        .iter()
+                        .map(|(condition, action)| TransitionDefinition {
+                            read: condition.0.clone(),
+                            write: action.write_values.clone(),
+                            move_to: action.move_to.clone(),
+                            next: action.continue_with,
+                        })
+                        .collect(),
+                )
+            })
+            .collect(),
+    };
+    Ok(serde_json::to_string_pretty(&definition)?)
+}
+
+pub(super) fn from_json(s: &str) -> anyhow::Result<Machine> {
+    let definition: MachineDefinition = serde_json::from_str(s)?;
+    let states = definition
+        .states
+        .into_iter()
+        .map(|(name, transitions)| {
+            let conditions = transitions
+                .into_iter()
+                .map(|transition| {
+                    (
+                        Condition(transition.read),
+                        StateAction::new(transition.write, transition.move_to, transition.next),
+                    )
+                })
+                .collect();
+            (name, State { conditions })
+        })
+        .collect();
+    let machine = Machine::new(definition.start, states, definition.steps)?;
+    let machine = match definition.halt {
+        Some(halt_state) => machine.with_halt_state(halt_state),
+        None => machine,
+    };
+    validate::check(&machine, &SourceLines::unknown(&machine))?;
+    Ok(machine)
+}
+
+fn symbol_count(machine: &Machine) -> Symbol {
+    machine.max_symbol().max(1) + 1
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_table_round_trip() {
+        for table in [
+            "1RB1LB_1LA1RZ",
+            "1RB1RZ_0RC1RB_1LC1LA",
+            "1RB1LB_1LA0LC_1RZ1LD_1RD0RA",
+            "1RB2LB1LZ_2LA2RB1LB",
+            "1RB---_0LA1RA",
+        ] {
+            let machine = from_table(table).unwrap();
+            assert_eq!(table, to_table(&machine).unwrap());
+        }
+    }
+
+    #[test]
+    fn test_table_halt_state() {
+        let mut machine = from_table("1RB1LB_1LA1RZ").unwrap();
+        assert_eq!(Some('Z'), machine.halt_state);
+        machine.checksum_after = 100;
+        assert_eq!(4, machine.run_until_checksum());
+        assert!(machine.is_halted());
+    }
+
+    #[test]
+    fn test_invalid_tables() {
+        assert!(from_table("1RB1LB_1LA").is_err());
+        assert!(from_table("1XB1LB_1LA1RZ").is_err());
+        assert!(from_table("1RB1LB_1LA1R?").is_err());
+        assert!(from_table("").is_err());
+        assert!(from_table(&vec!["1RA"; 27].join("_")).is_err());
+        let chain: Vec<String> = ('B'..='Z')
+            .chain(Some('A'))
+            .map(|next| format!("1R{}", next))
+            .collect();
+        assert!(from_table(&chain.join("_")).is_ok());
+        assert!(from_table(&vec!["1RA"; 26].join("_")).is_err());
+    }
+
+    #[test]
+    fn test_input_round_trip() {
+        let input = std::fs::read_to_string("input/input25.txt").unwrap();
+        let machine: Machine = input.parse().unwrap();
+        let table = to_table(&machine).unwrap();
+        let mut from_table = from_table(&table).unwrap();
+        from_table.checksum_after = machine.checksum_after;
+        assert_eq!(table, to_table(&from_table).unwrap());
+        assert_eq!(633, from_table.run_until_checksum());
+
+        let json = to_json(&machine).unwrap();
+        let mut from_json = from_json(&json).unwrap();
+        assert_eq!(json, to_json(&from_json).unwrap());
+        assert_eq!(633, from_json.run_until_checksum());
+
+        let text = machine.to_string();
+        let from_text: Machine = text.parse().unwrap();
+        assert_eq!(text, from_text.to_string());
+        assert_eq!(input.trim(), text);
+    }
+
+    #[test]
+    fn test_multiple_tapes_round_trip() {
+        let json = r#"{
+  "start": "A",
+  "halt": "H",
+  "steps": 10,
+  "states": {
+    "A": [
+      {
+        "read": [0, 0],
+        "write": [1, 2],
+        "move": ["right", "left"],
+        "next": "B"
+      }
+    ],
+    "B": [
+      {
+        "read": [0, 0],
+        "write": [1, 1],
+        "move": ["left", "left"],
+        "next": "H"
+      }
+    ]
+  }
+}"#;
+        let mut machine = parse_any(json).unwrap();
+        assert_eq!(2, machine.tapes.len());
+        assert!(to_table(&machine).is_err());
+        let text = machine.to_string();
+        assert_eq!(text, parse_any(&text).unwrap().to_string());
+        assert_eq!(
+            machine.to_string(),
+            parse_any(&to_json(&machine).unwrap()).unwrap().to_string()
+        );
+        assert_eq!(4, machine.run_until_checksum());
+    }
+}
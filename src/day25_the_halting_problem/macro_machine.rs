@@ -0,0 +1,433 @@
+use std::collections::HashMap;
+
+use anyhow::bail;
+
+use super::{Direction, Machine, StateAction, Symbol};
+
+type Block = u64;
+type Runs = Vec<(Block, u64)>;
+
+#[derive(Debug, Copy, Clone, Eq, PartialEq)]
+enum Exit {
+    Move(Direction),
+    Halt,
+    Undefined,
+    Loop { prefix: u64, period: u64 },
+    Stopped,
+}
+
+#[derive(Debug, Copy, Clone)]
+struct Transition {
+    state: char,
+    block: Block,
+    position: i32,
+    exit: Exit,
+    steps: u64,
+}
+
+pub(super) struct MacroMachine<'a> {
+    machine: &'a Machine,
+    block_size: u32,
+    bits: u32,
+    state: char,
+    side: Direction,
+    position: i32,
+    current: Block,
+    left: Runs,
+    right: Runs,
+    transitions: HashMap<(char, Direction, Block), Transition>,
+}
+
+impl<'a> MacroMachine<'a> {
+    pub fn new(machine: &'a Machine, block_size: u32) -> anyhow::Result<Self> {
+        if machine.tapes.len() != 1 {
+            bail!("only single tape machines can be accelerated");
+        }
+        let bits = (Symbol::BITS - machine.max_symbol().leading_zeros()).max(1);
+        if block_size == 0 || block_size * bits > Block::BITS {
+            bail!(
+                "block size {} does not fit {} bit symbols into a block",
+                block_size,
+                bits
+            );
+        }
+        Ok(Self {
+            machine,
+            block_size,
+            bits,
+            state: machine.state,
+            side: Direction::Left,
+            position: 0,
+            current: 0,
+            left: vec![],
+            right: vec![],
+            transitions: HashMap::new(),
+        })
+    }
+
+    pub fn run_until_checksum(&mut self) -> anyhow::Result<usize> {
+        self.run(self.machine.checksum_after)?;
+        Ok(self.checksum())
+    }
+
+    pub fn run(&mut self, steps: u64) -> anyhow::Result<()> {
+        let mut remaining = steps;
+        while remaining > 0 && Some(self.state) != self.machine.halt_state {
+            // a previous partial run can leave the head inside the block, which the
+            // cached transitions (keyed on the side the head entered from) do not cover
+            let transition = if self.position == self.start_position(self.side) {
+                self.transition(self.state, self.side, self.current)
+            } else {
+                self.simulate(self.state, self.position, self.current, u64::MAX)
+            };
+            if transition.steps > remaining {
+                return self.run_partial(remaining);
+            }
+            match transition.exit {
+                Exit::Move(direction) => {
+                    remaining -= transition.steps;
+                    self.state = transition.state;
+                    let (behind, _) = self.stacks(direction);
+                    push(behind, transition.block, 1);
+                    remaining = self.skip_runs(direction, remaining);
+                    let (_, ahead) = self.stacks(direction);
+                    self.current = pop(ahead);
+                    self.side = opposite(direction);
+                    self.position = self.start_position(self.side);
+                }
+                Exit::Halt => {
+                    self.state = transition.state;
+                    self.position = transition.position;
+                    self.current = transition.block;
+                }
+                Exit::Undefined if transition.steps < remaining => {
+                    bail!("no matching condition for state '{}'", transition.state)
+                }
+                Exit::Undefined | Exit::Loop { .. } | Exit::Stopped => {
+                    return self.run_partial(remaining)
+                }
+            }
+        }
+        Ok(())
+    }
+
+    pub fn checksum(&self) -> usize {
+        self.left
+            .iter()
+            .chain(self.right.iter())
+            .map(|&(block, count)| self.count_set(block) * count as usize)
+            .sum::<usize>()
+            + self.count_set(self.current)
+    }
+
+    fn stacks(&mut self, direction: Direction) -> (&mut Runs, &mut Runs) {
+        match direction {
+            Direction::Right => (&mut self.left, &mut self.right),
+            Direction::Left => (&mut self.right, &mut self.left),
+        }
+    }
+
+    fn skip_runs(&mut self, direction: Direction, mut remaining: u64) -> u64 {
+        let entered_from = opposite(direction);
+        loop {
+            let (block, count) = {
+                let (_, ahead) = self.stacks(direction);
+                ahead.last().copied().unwrap_or((0, u64::MAX))
+            };
+            let transition = self.transition(self.state, entered_from, block);
+            if transition.exit != Exit::Move(direction) || transition.state != self.state {
+                return remaining;
+            }
+            let skipped = count.min(remaining / transition.steps);
+            if skipped == 0 {
+                return remaining;
+            }
+            remaining -= skipped * transition.steps;
+            let (behind, ahead) = self.stacks(direction);
+            if let Some(top) = ahead.last_mut() {
+                top.1 -= skipped;
+                if top.1 == 0 {
+                    ahead.pop();
+                }
+            }
+            push(behind, transition.block, skipped);
+            if skipped < count {
+                return remaining;
+            }
+        }
+    }
+
+    fn run_partial(&mut self, remaining: u64) -> anyhow::Result<()> {
+        let position = self.position;
+        let transition = self.simulate(self.state, position, self.current, u64::MAX);
+        let steps = match transition.exit {
+            Exit::Loop { prefix, period } if remaining > prefix => {
+                prefix + (remaining - prefix) % period
+            }
+            _ => remaining,
+        };
+        let transition = self.simulate(self.state, position, self.current, steps);
+        if transition.exit == Exit::Undefined && transition.steps < steps {
+            bail!("no matching condition for state '{}'", transition.state);
+        }
+        self.state = transition.state;
+        self.position = transition.position;
+        self.current = transition.block;
+        Ok(())
+    }
+
+    fn transition(&mut self, state: char, side: Direction, block: Block) -> Transition {
+        if let Some(&transition) = self.transitions.get(&(state, side, block)) {
+            return transition;
+        }
+        let transition = self.simulate(state, self.start_position(side), block, u64::MAX);
+        self.transitions.insert((state, side, block), transition);
+        transition
+    }
+
+    fn simulate(
+        &self,
+        mut state: char,
+        mut position: i32,
+        mut block: Block,
+        max_steps: u64,
+    ) -> Transition {
+        let mut seen = HashMap::new();
+        let mut steps = 0;
+        let exit = loop {
+            if Some(state) == self.machine.halt_state {
+                break Exit::Halt;
+            }
+            if position < 0 {
+                break Exit::Move(Direction::Left);
+            }
+            if position >= self.block_size as i32 {
+                break Exit::Move(Direction::Right);
+            }
+            if steps == max_steps {
+                break Exit::Stopped;
+            }
+            if let Some(start) = seen.insert((state, position, block), steps) {
+                break Exit::Loop {
+                    prefix: start,
+                    period: steps - start,
+                };
+            }
+            let action = if let Some(action) = self.action(state, self.get(block, position)) {
+                action
+            } else {
+                break Exit::Undefined;
+            };
+            block = self.set(block, position, action.write_values[0]);
+            position += match action.move_to[0] {
+                Direction::Right => 1,
+                Direction::Left => -1,
+            };
+            state = action.continue_with;
+            steps += 1;
+        };
+        Transition {
+            state,
+            block,
+            position,
+            exit,
+            steps,
+        }
+    }
+
+    fn action(&self, state: char, symbol: Symbol) -> Option<&StateAction> {
+        self.machine
+            .states
+            .get(&state)?
+            .conditions
+            .iter()
+            .find(|(condition, _)| condition.0[0] == symbol)
+            .map(|(_, action)| action)
+    }
+
+    fn start_position(&self, side: Direction) -> i32 {
+        match side {
+            Direction::Left => 0,
+            Direction::Right => self.block_size as i32 - 1,
+        }
+    }
+
+    fn get(&self, block: Block, position: i32) -> Symbol {
+        let mask = (1 << self.bits) - 1;
+        ((block >> (position as u32 * self.bits)) & mask) as Symbol
+    }
+
+    fn set(&self, block: Block, position: i32, value: Symbol) -> Block {
+        let shift = position as u32 * self.bits;
+        let mask = ((1 << self.bits) - 1) << shift;
+        (block & !mask) | ((value as Block) << shift)
+    }
+
+    fn count_set(&self, block: Block) -> usize {
+        (0..self.block_size as i32)
+            .filter(|&position| self.get(block, position) != 0)
+            .count()
+    }
+}
+
+fn opposite(direction: Direction) -> Direction {
+    match direction {
+        Direction::Right => Direction::Left,
+        Direction::Left => Direction::Right,
+    }
+}
+
+fn push(stack: &mut Runs, block: Block, count: u64) {
+    match stack.last_mut() {
+        Some(top) if top.0 == block => top.1 += count,
+        None if block == 0 => {}
+        _ => stack.push((block, count)),
+    }
+}
+
+fn pop(stack: &mut Runs) -> Block {
+    if let Some(top) = stack.last_mut() {
+        let block = top.0;
+        top.1 -= 1;
+        if top.1 == 0 {
+            stack.pop();
+        }
+        block
+    } else {
+        0
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use utils::timer::Timer;
+
+    use super::super::tests::BUSY_BEAVER_2_STATES_3_SYMBOLS;
+    use super::*;
+
+    fn assert_same_checksum(input: &str, steps: u64) {
+        let mut machine: Machine = input.parse().unwrap();
+        machine.checksum_after = steps;
+        for block_size in 1..=8 {
+            let mut macro_machine = MacroMachine::new(&machine, block_size).unwrap();
+            assert_eq!(
+                machine.clone().run_until_checksum(),
+                macro_machine.run_until_checksum().unwrap(),
+                "block size {}, {} steps",
+                block_size,
+                steps
+            );
+        }
+    }
+
+    #[test]
+    fn test_input() {
+        let input = std::fs::read_to_string("input/input25.txt").unwrap();
+        for steps in [0, 1, 2, 3, 10, 99, 1_000, 12_345] {
+            assert_same_checksum(&input, steps);
+        }
+        let machine: Machine = input.parse().unwrap();
+        let mut macro_machine = MacroMachine::new(&machine, 8).unwrap();
+        assert_eq!(633, macro_machine.run_until_checksum().unwrap());
+    }
+
+    #[test]
+    fn test_multiple_symbols() {
+        for steps in 0..=38 {
+            assert_same_checksum(BUSY_BEAVER_2_STATES_3_SYMBOLS, steps);
+        }
+    }
+
+    #[test]
+    fn test_halting() {
+        let machine: Machine = BUSY_BEAVER_2_STATES_3_SYMBOLS.parse().unwrap();
+        let machine = machine.with_halt_state('Z');
+        let mut macro_machine = MacroMachine::new(&machine, 3).unwrap();
+        macro_machine.run(1_000).unwrap();
+        assert_eq!('Z', macro_machine.state);
+        assert_eq!(9, macro_machine.checksum());
+    }
+
+    #[test]
+    fn test_resume() {
+        let input = std::fs::read_to_string("input/input25.txt").unwrap();
+        for fixture in [input.as_str(), BUSY_BEAVER_2_STATES_3_SYMBOLS] {
+            let machine: Machine = fixture.parse().unwrap();
+            for block_size in [1, 3, 8] {
+                let mut plain = machine.clone();
+                let mut macro_machine = MacroMachine::new(&machine, block_size).unwrap();
+                for steps in [1, 2, 5, 3, 7, 13, 1, 100] {
+                    plain.checksum_after = steps;
+                    plain.run_until_checksum();
+                    macro_machine.run(steps).unwrap();
+                    assert_eq!(plain.state, macro_machine.state);
+                    assert_eq!(plain.checksum(), macro_machine.checksum());
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn test_loop_within_block() {
+        let input = "Begin in state A.
+Perform a diagnostic checksum after 1000001 steps.
+
+In state A:
+  If the current value is 0:
+    - Write the value 1.
+    - Move one slot to the right.
+    - Continue with state B.
+  If the current value is 1:
+    - Write the value 0.
+    - Move one slot to the right.
+    - Continue with state B.
+
+In state B:
+  If the current value is 0:
+    - Write the value 0.
+    - Move one slot to the left.
+    - Continue with state A.
+  If the current value is 1:
+    - Write the value 1.
+    - Move one slot to the left.
+    - Continue with state A.";
+
+        for steps in [5, 6, 7, 1_000_001] {
+            assert_same_checksum(input, steps);
+        }
+    }
+
+    #[test]
+    #[ignore]
+    fn benchmark() {
+        let input = std::fs::read_to_string("input/input25.txt").unwrap();
+        let mut machine: Machine = input.parse().unwrap();
+        let expected = {
+            let _timer =
+                Timer::start(|elapsed| println!("machine took {} ms.", elapsed.as_millis()));
+            machine.clone().run_until_checksum()
+        };
+        let actual = {
+            let _timer =
+                Timer::start(|elapsed| println!("macro machine took {} ms.", elapsed.as_millis()));
+            MacroMachine::new(&machine, 64)
+                .unwrap()
+                .run_until_checksum()
+                .unwrap()
+        };
+        assert_eq!(expected, actual);
+
+        machine.checksum_after = 1_000_000_000;
+        let _timer = Timer::start(|elapsed| {
+            println!(
+                "macro machine took {} ms for 1B steps.",
+                elapsed.as_millis()
+            )
+        });
+        let checksum = MacroMachine::new(&machine, 64)
+            .unwrap()
+            .run_until_checksum()
+            .unwrap();
+        println!("checksum after 1B steps: {}", checksum);
+    }
+}
@@ -0,0 +1,260 @@
+use anyhow::bail;
+
+use super::{format, Machine, Symbol, TapeBackend};
+
+pub(super) fn run_trace(args: &[String]) {
+    let mut machine = load(args.first());
+    if let Some(steps) = args.get(3) {
+        machine.checksum_after = steps.parse().expect("steps should be an integer");
+    }
+    let every = args
+        .get(1)
+        .map(|arg| arg.parse().expect("every should be an integer"))
+        .unwrap_or(1);
+    let radius = args
+        .get(2)
+        .map(|arg| arg.parse().expect("radius should be an integer"))
+        .unwrap_or(5);
+    for window in trace(&mut machine, every, radius).unwrap() {
+        println!("{}\n", window);
+    }
+}
+
+pub(super) fn run_diagram(args: &[String]) {
+    let mut machine = load(args.first());
+    let output = args
+        .get(1)
+        .expect("usage: diagram <file> <output.pgm> [steps] [every]");
+    let steps = args
+        .get(2)
+        .map(|arg| arg.parse().expect("steps should be an integer"))
+        .unwrap_or(machine.checksum_after);
+    let every = args
+        .get(3)
+        .map(|arg| arg.parse().expect("every should be an integer"))
+        .unwrap_or(1);
+    let image = space_time_diagram(&mut machine, 0, steps, every).unwrap();
+    std::fs::write(output, image).unwrap();
+    println!("wrote space-time diagram to {}", output);
+}
+
+fn load(path: Option<&String>) -> Machine {
+    let path = path
+        .map(|path| path.as_str())
+        .unwrap_or("input/input25.txt");
+    let input = std::fs::read_to_string(path).unwrap();
+    format::parse_any(&input).unwrap()
+}
+
+pub(super) fn trace(machine: &mut Machine, every: u64, radius: i32) -> anyhow::Result<Vec<String>> {
+    if every == 0 {
+        bail!("every must be at least 1");
+    }
+    if machine.checksum_after == 0 {
+        bail!("machine has no step count, pass the number of steps to trace");
+    }
+    let mut windows = Vec::new();
+    for step in 0..=machine.checksum_after {
+        let last = step == machine.checksum_after || machine.is_halted();
+        if step % every == 0 || last {
+            windows.push(render_window(machine, step, radius));
+        }
+        if last {
+            break;
+        }
+        machine.step()?;
+    }
+    Ok(windows)
+}
+
+pub(super) fn render_window(machine: &Machine, step: u64, radius: i32) -> String {
+    let annotation = if machine.is_halted() {
+        format!("(after {} steps; halted in state {})", step, machine.state)
+    } else {
+        format!(
+            "(after {} steps; about to run state {})",
+            step, machine.state
+        )
+    };
+    machine
+        .tapes
+        .iter()
+        .enumerate()
+        .map(|(i, tape)| {
+            let (first, values) = tape.contents();
+            let cursor = tape.cursor();
+            let cells: String = (cursor - radius..=cursor + radius)
+                .map(|position| {
+                    let value = value_at(first, &values, position);
+                    if position == cursor {
+                        format!("[{}]", value)
+                    } else {
+                        format!(" {} ", value)
+                    }
+                })
+                .collect();
+            if i == 0 {
+                format!("...{}... {}", cells, annotation)
+            } else {
+                format!("...{}...", cells)
+            }
+        })
+        .collect::<Vec<_>>()
+        .join("\n")
+}
+
+pub(super) fn space_time_diagram(
+    machine: &mut Machine,
+    tape: usize,
+    steps: u64,
+    every: u64,
+) -> anyhow::Result<Vec<u8>> {
+    if tape >= machine.tapes.len() {
+        bail!("machine has no tape {}", tape);
+    }
+    if every == 0 {
+        bail!("every must be at least 1");
+    }
+    if steps == 0 {
+        bail!("no steps to draw, pass the number of steps");
+    }
+    let mut rows = Vec::new();
+    for step in 0..=steps {
+        let last = step == steps || machine.is_halted();
+        if step % every == 0 || last {
+            let (first, values) = machine.tapes[tape].contents();
+            rows.push((first, values, machine.tapes[tape].cursor()));
+        }
+        if last {
+            break;
+        }
+        machine.step()?;
+    }
+
+    let min = rows
+        .iter()
+        .map(|(first, _, cursor)| *first.min(cursor))
+        .min()
+        .unwrap();
+    let max = rows
+        .iter()
+        .map(|(first, values, cursor)| (first + values.len() as i32 - 1).max(*cursor))
+        .max()
+        .unwrap();
+    let max_symbol = rows
+        .iter()
+        .flat_map(|(_, values, _)| values.iter())
+        .copied()
+        .max()
+        .unwrap_or_default()
+        .max(1);
+
+    let width = (max - min + 1) as usize;
+    let mut image = format!("P5\n{} {}\n255\n", width, rows.len()).into_bytes();
+    for (first, values, cursor) in rows.iter() {
+        image.extend((min..=max).map(|position| {
+            if position == *cursor {
+                0
+            } else {
+                let value = value_at(*first, values, position) as u32;
+                (255 - value * 191 / max_symbol as u32) as u8
+            }
+        }));
+    }
+    Ok(image)
+}
+
+fn value_at(first: i32, values: &[Symbol], position: i32) -> Symbol {
+    let index = position - first;
+    if index < 0 {
+        0
+    } else {
+        values.get(index as usize).copied().unwrap_or_default()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const INPUT: &str = "Begin in state A.
+Perform a diagnostic checksum after 6 steps.
+
+In state A:
+  If the current value is 0:
+    - Write the value 1.
+    - Move one slot to the right.
+    - Continue with state B.
+  If the current value is 1:
+    - Write the value 0.
+    - Move one slot to the left.
+    - Continue with state B.
+
+In state B:
+  If the current value is 0:
+    - Write the value 1.
+    - Move one slot to the left.
+    - Continue with state A.
+  If the current value is 1:
+    - Write the value 1.
+    - Move one slot to the right.
+    - Continue with state A.";
+
+    #[test]
+    fn test_render_window() {
+        let mut machine: Machine = INPUT.parse().unwrap();
+        assert_eq!(
+            "... 0  0  0 [0] 0  0  0 ... (after 0 steps; about to run state A)",
+            render_window(&machine, 0, 3)
+        );
+        for _ in 0..3 {
+            machine.step().unwrap();
+        }
+        assert_eq!(
+            "... 0  0  0 [0] 0  1  0 ... (after 3 steps; about to run state B)",
+            render_window(&machine, 3, 3)
+        );
+        for _ in 3..6 {
+            machine.step().unwrap();
+        }
+        assert_eq!(
+            "... 0  1  1 [0] 1  0  0 ... (after 6 steps; about to run state A)",
+            render_window(&machine, 6, 3)
+        );
+    }
+
+    #[test]
+    fn test_space_time_diagram() {
+        let mut machine: Machine = INPUT.parse().unwrap();
+        assert!(space_time_diagram(&mut machine.clone(), 0, 6, 0).is_err());
+        assert!(space_time_diagram(&mut machine.clone(), 0, 0, 1).is_err());
+        let image = space_time_diagram(&mut machine, 0, 6, 2).unwrap();
+        let header = b"P5\n4 4\n255\n";
+        assert_eq!(header, &image[..header.len()]);
+        assert_eq!(header.len() + 4 * 4, image.len());
+        assert_eq!(&[255, 255, 0, 255], &image[header.len()..header.len() + 4]);
+        assert_eq!(3, machine.checksum());
+    }
+
+    #[test]
+    fn test_trace() {
+        let machine: Machine = INPUT.parse().unwrap();
+        assert!(trace(&mut machine.clone(), 0, 3).is_err());
+        assert_eq!(
+            vec![
+                "... 0  0  0 [0] 0  0  0 ... (after 0 steps; about to run state A)",
+                "... 0  0  0 [0] 0  1  0 ... (after 3 steps; about to run state B)",
+                "... 0  1  1 [0] 1  0  0 ... (after 6 steps; about to run state A)",
+            ],
+            trace(&mut machine.clone(), 3, 3).unwrap()
+        );
+        assert_eq!(3, trace(&mut machine.clone(), 4, 3).unwrap().len());
+
+        let mut from_table = format::from_table("1RB1LB_1LA1RZ").unwrap();
+        assert!(trace(&mut from_table.clone(), 1, 3).is_err());
+        from_table.checksum_after = 10;
+        let windows = trace(&mut from_table, 1, 3).unwrap();
+        assert_eq!(7, windows.len());
+        assert!(windows[6].ends_with("(after 6 steps; halted in state Z)"));
+    }
+}
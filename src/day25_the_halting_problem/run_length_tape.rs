@@ -0,0 +1,177 @@
+use super::{Direction, Symbol, TapeBackend};
+
+type Runs = Vec<(Symbol, u32)>;
+
+#[derive(Clone)]
+pub(super) struct RunLengthTape {
+    cursor: i32,
+    current: Symbol,
+    left: Runs,
+    right: Runs,
+    count_set: usize,
+}
+
+impl RunLengthTape {
+    pub fn new() -> Self {
+        Self {
+            cursor: 0,
+            current: 0,
+            left: vec![],
+            right: vec![],
+            count_set: 0,
+        }
+    }
+}
+
+impl TapeBackend for RunLengthTape {
+    fn cursor(&self) -> i32 {
+        self.cursor
+    }
+    fn move_to(&mut self, direction: Direction) {
+        let (behind, ahead) = match direction {
+            Direction::Right => {
+                self.cursor += 1;
+                (&mut self.left, &mut self.right)
+            }
+            Direction::Left => {
+                self.cursor -= 1;
+                (&mut self.right, &mut self.left)
+            }
+        };
+        push(behind, self.current);
+        self.current = pop(ahead);
+    }
+    fn get(&self) -> Symbol {
+        self.current
+    }
+    fn set(&mut self, value: Symbol) {
+        match (self.current != 0, value != 0) {
+            (false, true) => self.count_set += 1,
+            (true, false) => self.count_set -= 1,
+            _ => {}
+        }
+        self.current = value;
+    }
+    fn count_set(&self) -> usize {
+        self.count_set
+    }
+    fn contents(&self) -> (i32, Vec<Symbol>) {
+        let expand = |runs: &Runs| -> Vec<Symbol> {
+            runs.iter()
+                .flat_map(|&(value, count)| vec![value; count as usize])
+                .collect()
+        };
+        let mut values = expand(&self.left);
+        let first = self.cursor - values.len() as i32;
+        values.push(self.current);
+        values.extend(expand(&self.right).iter().rev());
+        (first, values)
+    }
+}
+
+fn push(runs: &mut Runs, value: Symbol) {
+    match runs.last_mut() {
+        Some(top) if top.0 == value => top.1 += 1,
+        None if value == 0 => {}
+        _ => runs.push((value, 1)),
+    }
+}
+
+fn pop(runs: &mut Runs) -> Symbol {
+    if let Some(top) = runs.last_mut() {
+        let value = top.0;
+        top.1 -= 1;
+        if top.1 == 0 {
+            runs.pop();
+        }
+        value
+    } else {
+        0
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use utils::timer::Timer;
+
+    use super::super::{Machine, Tape, VecTape};
+    use super::*;
+
+    #[test]
+    fn test_matches_vec_tape() {
+        let mut vec_tape: Tape = VecTape::new().into();
+        let mut run_length_tape: Tape = RunLengthTape::new().into();
+        let moves = [
+            (1, Direction::Right),
+            (1, Direction::Right),
+            (2, Direction::Left),
+            (0, Direction::Left),
+            (0, Direction::Left),
+            (1, Direction::Left),
+            (1, Direction::Right),
+            (0, Direction::Right),
+            (2, Direction::Right),
+            (2, Direction::Right),
+            (0, Direction::Right),
+        ];
+        for (value, direction) in moves {
+            for tape in [&mut vec_tape, &mut run_length_tape] {
+                tape.set(value);
+                tape.move_to(direction);
+            }
+            assert_eq!(vec_tape.cursor(), run_length_tape.cursor());
+            assert_eq!(vec_tape.get(), run_length_tape.get());
+            assert_eq!(vec_tape.count_set(), run_length_tape.count_set());
+        }
+        let (vec_first, vec_values) = vec_tape.contents();
+        let (first, values) = run_length_tape.contents();
+        for position in vec_first.min(first)..vec_first.max(first) + vec_values.len() as i32 {
+            let at = |first: i32, values: &[Symbol]| -> Symbol {
+                let index = position - first;
+                if index < 0 {
+                    0
+                } else {
+                    values.get(index as usize).copied().unwrap_or_default()
+                }
+            };
+            assert_eq!(at(vec_first, &vec_values), at(first, &values));
+        }
+    }
+
+    #[test]
+    fn test_input() {
+        let input = std::fs::read_to_string("input/input25.txt").unwrap();
+        let machine: Machine = input.parse().unwrap();
+        for steps in [0, 1, 10, 1_000, 100_000] {
+            let mut machine = machine.clone();
+            machine.checksum_after = steps;
+            assert_eq!(
+                machine.clone().run_until_checksum(),
+                machine
+                    .with_tapes(|| RunLengthTape::new().into())
+                    .run_until_checksum()
+            );
+        }
+    }
+
+    #[test]
+    #[ignore]
+    fn benchmark() {
+        let input = std::fs::read_to_string("input/input25.txt").unwrap();
+        let machine: Machine = input.parse().unwrap();
+        let expected = {
+            let _timer =
+                Timer::start(|elapsed| println!("vec tape took {} ms.", elapsed.as_millis()));
+            machine.clone().run_until_checksum()
+        };
+        let actual = {
+            let _timer = Timer::start(|elapsed| {
+                println!("run length tape took {} ms.", elapsed.as_millis())
+            });
+            machine
+                .with_tapes(|| RunLengthTape::new().into())
+                .run_until_checksum()
+        };
+        assert_eq!(expected, actual);
+    }
+}
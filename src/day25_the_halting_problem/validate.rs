@@ -0,0 +1,404 @@
+use std::collections::{BTreeSet, HashMap, HashSet, VecDeque};
+
+use anyhow::bail;
+use thiserror::Error;
+
+use super::{Machine, Symbol};
+
+#[derive(Debug, Default)]
+pub(super) struct SourceLines {
+    pub states: HashMap<char, usize>,
+    pub conditions: HashMap<char, Vec<(usize, usize)>>,
+    pub duplicate_states: Vec<(char, usize)>,
+}
+
+impl SourceLines {
+    // for formats without meaningful line numbers, problems are reported at line 0
+    pub fn unknown(machine: &Machine) -> Self {
+        Self {
+            states: machine.states.keys().map(|&name| (name, 0)).collect(),
+            conditions: machine
+                .states
+                .iter()
+                .map(|(&name, state)| (name, vec![(0, 0); state.conditions.len()]))
+                .collect(),
+            duplicate_states: vec![],
+        }
+    }
+}
+
+#[derive(Debug, Error, Eq, PartialEq)]
+pub(super) enum Problem {
+    #[error("{}state {state} is not defined", at(.line))]
+    UndefinedState { line: usize, state: char },
+    #[error("{}state {state} is defined more than once", at(.line))]
+    DuplicateState { line: usize, state: char },
+    #[error("{}state {state} is not reachable from the start state", at(.line))]
+    UnreachableState { line: usize, state: char },
+    #[error("{}state {state} has more than one condition for {values:?}", at(.line))]
+    DuplicateCondition {
+        line: usize,
+        state: char,
+        values: Vec<Symbol>,
+    },
+    #[error("{}state {state} has no condition for {values:?}", at(.line))]
+    MissingBranch {
+        line: usize,
+        state: char,
+        values: Vec<Symbol>,
+    },
+}
+
+impl Problem {
+    pub fn line(&self) -> usize {
+        match self {
+            Problem::UndefinedState { line, .. }
+            | Problem::DuplicateState { line, .. }
+            | Problem::UnreachableState { line, .. }
+            | Problem::DuplicateCondition { line, .. }
+            | Problem::MissingBranch { line, .. } => *line,
+        }
+    }
+
+    // partial machines such as busy beaver tables are allowed to leave branches out
+    pub fn is_warning(&self) -> bool {
+        matches!(self, Problem::MissingBranch { .. })
+    }
+}
+
+fn at(line: &usize) -> String {
+    if *line == 0 {
+        String::new()
+    } else {
+        format!("line {}: ", line)
+    }
+}
+
+pub(super) fn check(machine: &Machine, source_lines: &SourceLines) -> anyhow::Result<()> {
+    let (warnings, errors): (Vec<Problem>, Vec<Problem>) = validate(machine, source_lines)
+        .into_iter()
+        .partition(Problem::is_warning);
+    // warnings are shown even without a logger, partial machines still load
+    for warning in warnings {
+        eprintln!("warning: {}", warning);
+    }
+    if !errors.is_empty() {
+        bail!(
+            "invalid machine:\n{}",
+            errors
+                .iter()
+                .map(|problem| problem.to_string())
+                .collect::<Vec<_>>()
+                .join("\n")
+        );
+    }
+    Ok(())
+}
+
+pub(super) fn validate(machine: &Machine, source_lines: &SourceLines) -> Vec<Problem> {
+    let is_defined =
+        |state: char| machine.states.contains_key(&state) || machine.halt_state == Some(state);
+    let mut problems = Vec::new();
+    if !is_defined(machine.state) {
+        problems.push(Problem::UndefinedState {
+            line: 1,
+            state: machine.state,
+        });
+    }
+    problems.extend(
+        source_lines
+            .duplicate_states
+            .iter()
+            .map(|&(state, line)| Problem::DuplicateState { line, state }),
+    );
+
+    let alphabets = alphabets(machine);
+    let branches = combinations(&alphabets);
+    for (&name, state) in machine.states.iter() {
+        let mut seen = HashSet::new();
+        for ((condition, action), &(condition_line, continue_line)) in state
+            .conditions
+            .iter()
+            .zip(source_lines.conditions[&name].iter())
+        {
+            if !seen.insert(&condition.0) {
+                problems.push(Problem::DuplicateCondition {
+                    line: condition_line,
+                    state: name,
+                    values: condition.0.clone(),
+                });
+            }
+            if !is_defined(action.continue_with) {
+                problems.push(Problem::UndefinedState {
+                    line: continue_line,
+                    state: action.continue_with,
+                });
+            }
+        }
+        problems.extend(
+            branches
+                .iter()
+                .filter(|values| !seen.contains(values))
+                .map(|values| Problem::MissingBranch {
+                    line: source_lines.states[&name],
+                    state: name,
+                    values: values.clone(),
+                }),
+        );
+    }
+
+    let reachable = reachable(machine);
+    problems.extend(
+        machine
+            .states
+            .keys()
+            .filter(|state| !reachable.contains(state))
+            .map(|&state| Problem::UnreachableState {
+                line: source_lines.states[&state],
+                state,
+            }),
+    );
+
+    problems.sort_by_key(|problem| (problem.line(), problem.to_string()));
+    problems
+}
+
+fn alphabets(machine: &Machine) -> Vec<BTreeSet<Symbol>> {
+    let mut alphabets = vec![BTreeSet::from([0]); machine.tapes.len()];
+    for (condition, action) in machine
+        .states
+        .values()
+        .flat_map(|state| state.conditions.iter())
+    {
+        for (tape, (&read, &write)) in condition
+            .0
+            .iter()
+            .zip(action.write_values.iter())
+            .enumerate()
+        {
+            alphabets[tape].insert(read);
+            alphabets[tape].insert(write);
+        }
+    }
+    alphabets
+}
+
+fn combinations(alphabets: &[BTreeSet<Symbol>]) -> Vec<Vec<Symbol>> {
+    alphabets
+        .iter()
+        .fold(vec![vec![]], |combinations, alphabet| {
+            combinations
+                .iter()
+                .flat_map(|prefix| {
+                    alphabet.iter().map(move |&symbol| {
+                        let mut combination = prefix.clone();
+                        combination.push(symbol);
+                        combination
+                    })
+                })
+                .collect()
+        })
+}
+
+fn reachable(machine: &Machine) -> HashSet<char> {
+    let mut reachable = HashSet::from([machine.state]);
+    let mut queue = VecDeque::from([machine.state]);
+    while let Some(state) = queue.pop_front() {
+        if let Some(state) = machine.states.get(&state) {
+            for (_, action) in state.conditions.iter() {
+                if reachable.insert(action.continue_with) {
+                    queue.push_back(action.continue_with);
+                }
+            }
+        }
+    }
+    reachable
+}
+
+#[cfg(test)]
+mod tests {
+    use super::super::{format, parse_machine};
+    use super::*;
+
+    fn problems(input: &str) -> Vec<Problem> {
+        let (machine, source_lines) = parse_machine(input).unwrap();
+        validate(&machine, &source_lines)
+    }
+
+    #[test]
+    fn test_valid() {
+        let input = std::fs::read_to_string("input/input25.txt").unwrap();
+        assert_eq!(Vec::<Problem>::new(), problems(&input));
+    }
+
+    #[test]
+    fn test_all_problems() {
+        let input = "Begin in state A.
+Perform a diagnostic checksum after 6 steps.
+
+In state A:
+  If the current value is 0:
+    - Write the value 1.
+    - Move one slot to the right.
+    - Continue with state B.
+  If the current value is 0:
+    - Write the value 0.
+    - Move one slot to the left.
+    - Continue with state Q.
+
+In state B:
+  If the current value is 0:
+    - Write the value 1.
+    - Move one slot to the left.
+    - Continue with state A.
+  If the current value is 1:
+    - Write the value 1.
+    - Move one slot to the right.
+    - Continue with state A.
+
+In state C:
+  If the current value is 0:
+    - Write the value 1.
+    - Move one slot to the left.
+    - Continue with state A.
+  If the current value is 1:
+    - Write the value 1.
+    - Move one slot to the right.
+    - Continue with state A.
+
+In state B:
+  If the current value is 0:
+    - Write the value 1.
+    - Move one slot to the left.
+    - Continue with state A.";
+
+        assert_eq!(
+            vec![
+                Problem::MissingBranch {
+                    line: 4,
+                    state: 'A',
+                    values: vec![1]
+                },
+                Problem::DuplicateCondition {
+                    line: 9,
+                    state: 'A',
+                    values: vec![0]
+                },
+                Problem::UndefinedState {
+                    line: 12,
+                    state: 'Q'
+                },
+                Problem::UnreachableState {
+                    line: 24,
+                    state: 'C'
+                },
+                Problem::DuplicateState {
+                    line: 34,
+                    state: 'B'
+                },
+            ],
+            problems(input)
+        );
+
+        let error = input.parse::<Machine>().err().unwrap().to_string();
+        assert!(error.contains("line 12: state Q is not defined"));
+        assert!(error.contains("line 24: state C is not reachable from the start state"));
+        assert!(!error.contains("has no condition"));
+    }
+
+    #[test]
+    fn test_halt_state() {
+        let input = "Begin in state A.
+Perform a diagnostic checksum after 6 steps.
+
+In state A:
+  If the current value is 0:
+    - Write the value 1.
+    - Move one slot to the right.
+    - Continue with state Z.
+  If the current value is 1:
+    - Write the value 0.
+    - Move one slot to the left.
+    - Continue with state A.";
+
+        assert_eq!(
+            vec![Problem::UndefinedState {
+                line: 8,
+                state: 'Z'
+            }],
+            problems(input)
+        );
+        let with_halt_state = input.replacen("steps.\n", "steps.\nHalt in state Z.\n", 1);
+        assert_eq!(Vec::<Problem>::new(), problems(&with_halt_state));
+        let machine: Machine = with_halt_state.parse().unwrap();
+        assert_eq!(with_halt_state, machine.to_string());
+    }
+
+    #[test]
+    fn test_missing_branch_is_a_warning() {
+        let input = "Begin in state A.
+Perform a diagnostic checksum after 6 steps.
+Halt in state H.
+
+In state A:
+  If the current value is 0:
+    - Write the value 1.
+    - Move one slot to the right.
+    - Continue with state H.";
+
+        assert_eq!(
+            vec![Problem::MissingBranch {
+                line: 5,
+                state: 'A',
+                values: vec![1]
+            }],
+            problems(input)
+        );
+        let machine: Machine = input.parse().unwrap();
+        assert_eq!(input, machine.to_string());
+    }
+
+    #[test]
+    fn test_other_formats() {
+        let machine = format::from_table("1RB---_0LA1RA").unwrap();
+        assert_eq!(
+            vec![Problem::MissingBranch {
+                line: 0,
+                state: 'A',
+                values: vec![1]
+            }],
+            validate(&machine, &SourceLines::unknown(&machine))
+        );
+        assert_eq!(
+            "state A has no condition for [1]",
+            validate(&machine, &SourceLines::unknown(&machine))[0].to_string()
+        );
+
+        let error = format::from_table("1RB1LY_1LA1RZ")
+            .err()
+            .unwrap()
+            .to_string();
+        assert!(error.contains("state Y is not defined"));
+        assert!(error.contains("state Z is not defined"));
+        let json = format::to_json(
+            &"Begin in state A.
+Perform a diagnostic checksum after 6 steps.
+Halt in state H.
+
+In state A:
+  If the current value is 0:
+    - Write the value 1.
+    - Move one slot to the right.
+    - Continue with state H."
+                .parse()
+                .unwrap(),
+        )
+        .unwrap();
+        assert!(format::from_json(&json).is_ok());
+        let error = format::from_json(&json.replace("\"halt\": \"H\",", ""))
+            .err()
+            .unwrap()
+            .to_string();
+        assert_eq!("invalid machine:\nstate H is not defined", error);
+    }
+}
@@ -1,5 +1,6 @@
 use utils::timer::Timer;
 
+mod bit_grid;
 mod day01_inverse_captcha;
 mod day02_corruption_checksum;
 mod day03_spiral_memory;
@@ -25,9 +26,15 @@ mod day22_sporifica_virus;
 mod day23_coprocessor_conflagration;
 mod day24_electromagnetic_moat;
 mod day25_the_halting_problem;
+mod primes;
+mod union_find;
 
 fn main() {
     env_logger::init();
+    if std::env::args().nth(1).as_deref() == Some("knothash") {
+        let args: Vec<String> = std::env::args().skip(2).collect();
+        std::process::exit(day10_knot_hash::run_knothash(&args));
+    }
     let _timer = Timer::start(|elapsed| println!("main took {} ms.", elapsed.as_millis()));
     let day: usize = if let Some(arg1) = std::env::args().nth(1) {
         arg1.parse().expect("argument should be an integer")
@@ -0,0 +1,223 @@
+use std::sync::Mutex;
+
+use lazy_static::lazy_static;
+
+const SIEVE_LIMIT: u64 = 1 << 24;
+const SEGMENT_SIZE: u64 = 1 << 16;
+const MILLER_RABIN_BASES: [u64; 12] = [2, 3, 5, 7, 11, 13, 17, 19, 23, 29, 31, 37];
+
+lazy_static! {
+    static ref SIEVE: Mutex<Sieve> = Mutex::new(Sieve::new());
+}
+
+pub(crate) fn is_prime(n: u64) -> bool {
+    if n < SIEVE_LIMIT {
+        SIEVE.lock().unwrap().is_prime(n)
+    } else {
+        miller_rabin(n)
+    }
+}
+
+struct Sieve {
+    base_primes: Vec<u64>,
+    composite: Vec<u64>,
+    sieved_up_to: u64,
+}
+
+impl Sieve {
+    pub fn new() -> Self {
+        let root = integer_sqrt(SIEVE_LIMIT) + 1;
+        let mut is_composite = vec![false; root as usize + 1];
+        let mut base_primes = Vec::new();
+        for p in 2..=root {
+            if is_composite[p as usize] {
+                continue;
+            }
+            base_primes.push(p);
+            for multiple in (p * p..=root).step_by(p as usize) {
+                is_composite[multiple as usize] = true;
+            }
+        }
+        Self {
+            base_primes,
+            composite: Vec::new(),
+            sieved_up_to: 0,
+        }
+    }
+
+    pub fn is_prime(&mut self, n: u64) -> bool {
+        if n < 2 {
+            return false;
+        }
+        while n >= self.sieved_up_to {
+            self.sieve_next_segment();
+        }
+        self.composite[(n / 64) as usize] & (1 << (n % 64)) == 0
+    }
+
+    fn sieve_next_segment(&mut self) {
+        let low = self.sieved_up_to;
+        let high = (low + SEGMENT_SIZE).min(SIEVE_LIMIT);
+        self.composite.resize((high / 64) as usize + 1, 0);
+        for &p in self.base_primes.iter().take_while(|&&p| p * p < high) {
+            let first = (p * p).max(low.div_ceil(p) * p);
+            for multiple in (first..high).step_by(p as usize) {
+                self.composite[(multiple / 64) as usize] |= 1 << (multiple % 64);
+            }
+        }
+        self.sieved_up_to = high;
+    }
+}
+
+fn integer_sqrt(n: u64) -> u64 {
+    let mut root = (n as f64).sqrt() as u64;
+    while root * root > n {
+        root -= 1;
+    }
+    while (root + 1) * (root + 1) <= n {
+        root += 1;
+    }
+    root
+}
+
+fn miller_rabin(n: u64) -> bool {
+    if n < 2 {
+        return false;
+    }
+    for &p in MILLER_RABIN_BASES.iter() {
+        if n.is_multiple_of(p) {
+            return n == p;
+        }
+    }
+    let mut d = n - 1;
+    let mut s = 0;
+    while d.is_multiple_of(2) {
+        d /= 2;
+        s += 1;
+    }
+    'bases: for &a in MILLER_RABIN_BASES.iter() {
+        let mut x = pow_mod(a, d, n);
+        if x == 1 || x == n - 1 {
+            continue;
+        }
+        for _ in 1..s {
+            x = mul_mod(x, x, n);
+            if x == n - 1 {
+                continue 'bases;
+            }
+        }
+        return false;
+    }
+    true
+}
+
+fn mul_mod(a: u64, b: u64, m: u64) -> u64 {
+    ((a as u128 * b as u128) % m as u128) as u64
+}
+
+fn pow_mod(mut base: u64, mut exponent: u64, m: u64) -> u64 {
+    let mut result = 1;
+    base %= m;
+    while exponent > 0 {
+        if exponent & 1 == 1 {
+            result = mul_mod(result, base, m);
+        }
+        base = mul_mod(base, base, m);
+        exponent >>= 1;
+    }
+    result
+}
+
+#[cfg(test)]
+mod tests {
+    use utils::timer::Timer;
+
+    use super::*;
+
+    fn is_prime_by_full_sieve(n: usize) -> bool {
+        let mut not_prime = vec![false; n + 1];
+        let mut last = 1;
+        while last < n {
+            let p = if let Some(p) = not_prime
+                .iter()
+                .enumerate()
+                .skip(last + 1)
+                .find(|(_, &marked)| !marked)
+                .map(|(p, _)| p)
+            {
+                p
+            } else {
+                break;
+            };
+            for i in 2..=n / p {
+                not_prime[i * p] = true;
+            }
+            last = p;
+        }
+
+        !not_prime[n]
+    }
+
+    #[test]
+    fn test_small_numbers() {
+        assert!(!is_prime(0));
+        assert!(!is_prime(1));
+        for n in 2..3000u64 {
+            assert_eq!(is_prime(n), is_prime_by_full_sieve(n as usize), "n = {}", n);
+        }
+    }
+
+    #[test]
+    fn test_segment_boundaries() {
+        assert!(is_prime(65537));
+        assert!(!is_prime(65536));
+        assert!(is_prime(16777213));
+        assert!(!is_prime(16777215));
+        assert!(!is_prime(SIEVE_LIMIT));
+    }
+
+    #[test]
+    fn test_sieve_agrees_with_miller_rabin() {
+        for n in (106_500..125_000u64).chain(SIEVE_LIMIT - 5000..SIEVE_LIMIT) {
+            assert_eq!(is_prime(n), miller_rabin(n), "n = {}", n);
+        }
+    }
+
+    #[test]
+    fn test_large_numbers() {
+        assert!(is_prime(2_305_843_009_213_693_951));
+        assert!(is_prime(18_446_744_073_709_551_557));
+        assert!(!is_prime(u64::MAX));
+        assert!(!is_prime(3_215_031_751));
+        assert!(!is_prime(3_825_123_056_546_413_051));
+        assert!(!is_prime(4_294_967_297));
+    }
+
+    #[test]
+    #[ignore]
+    fn benchmark() {
+        let numbers: Vec<u64> = (106_500..=123_500).step_by(17).collect();
+        let full_sieve_count = {
+            let _timer = Timer::start(|elapsed| {
+                println!("full sieve per call took {} ms.", elapsed.as_millis())
+            });
+            numbers
+                .iter()
+                .filter(|&&n| is_prime_by_full_sieve(n as usize))
+                .count()
+        };
+        let cached_count = {
+            let _timer = Timer::start(|elapsed| {
+                println!("cached segmented sieve took {} ms.", elapsed.as_millis())
+            });
+            numbers.iter().filter(|&&n| is_prime(n)).count()
+        };
+        let miller_rabin_count = {
+            let _timer =
+                Timer::start(|elapsed| println!("miller-rabin took {} ms.", elapsed.as_millis()));
+            numbers.iter().filter(|&&n| miller_rabin(n)).count()
+        };
+        assert_eq!(full_sieve_count, cached_count);
+        assert_eq!(full_sieve_count, miller_rabin_count);
+    }
+}
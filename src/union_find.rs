@@ -0,0 +1,134 @@
+#[derive(Debug, Clone)]
+pub(crate) struct UnionFind {
+    parent: Vec<usize>,
+    rank: Vec<u8>,
+    sets: usize,
+}
+
+impl UnionFind {
+    pub fn new(len: usize) -> Self {
+        Self {
+            parent: (0..len).collect(),
+            rank: vec![0; len],
+            sets: len,
+        }
+    }
+
+    pub fn len(&self) -> usize {
+        self.parent.len()
+    }
+
+    pub fn sets(&self) -> usize {
+        self.sets
+    }
+
+    pub fn find(&mut self, element: usize) -> usize {
+        let mut root = element;
+        while self.parent[root] != root {
+            root = self.parent[root];
+        }
+        let mut current = element;
+        while self.parent[current] != root {
+            let next = self.parent[current];
+            self.parent[current] = root;
+            current = next;
+        }
+        root
+    }
+
+    pub fn union(&mut self, a: usize, b: usize) -> bool {
+        let a = self.find(a);
+        let b = self.find(b);
+        if a == b {
+            return false;
+        }
+        let (parent, child) = if self.rank[a] < self.rank[b] {
+            (b, a)
+        } else {
+            (a, b)
+        };
+        self.parent[child] = parent;
+        if self.rank[parent] == self.rank[child] {
+            self.rank[parent] += 1;
+        }
+        self.sets -= 1;
+        true
+    }
+
+    pub fn connected(&mut self, a: usize, b: usize) -> bool {
+        self.find(a) == self.find(b)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::VecDeque;
+
+    use proptest::prelude::*;
+
+    use super::*;
+
+    fn flood_fill(len: usize, edges: &[(usize, usize)]) -> Vec<usize> {
+        let mut neighbors = vec![vec![]; len];
+        for &(a, b) in edges {
+            neighbors[a].push(b);
+            neighbors[b].push(a);
+        }
+        let mut component = vec![usize::MAX; len];
+        for start in 0..len {
+            if component[start] != usize::MAX {
+                continue;
+            }
+            component[start] = start;
+            let mut queue = VecDeque::from([start]);
+            while let Some(next) = queue.pop_front() {
+                for &neighbor in neighbors[next].iter() {
+                    if component[neighbor] == usize::MAX {
+                        component[neighbor] = start;
+                        queue.push_back(neighbor);
+                    }
+                }
+            }
+        }
+        component
+    }
+
+    #[test]
+    fn test_union() {
+        let mut sets = UnionFind::new(5);
+        assert_eq!(5, sets.sets());
+        assert!(sets.union(0, 1));
+        assert!(sets.union(3, 4));
+        assert!(!sets.union(1, 0));
+        assert!(sets.connected(0, 1));
+        assert!(!sets.connected(1, 3));
+        assert!(sets.union(1, 4));
+        assert!(sets.connected(0, 3));
+        assert_eq!(2, sets.sets());
+        assert_eq!(5, sets.len());
+    }
+
+    proptest! {
+        #[test]
+        fn test_same_as_flood_fill(
+            (len, edges) in (1..60usize).prop_flat_map(|len| {
+                (Just(len), prop::collection::vec((0..len, 0..len), 0..80))
+            })
+        ) {
+            let mut sets = UnionFind::new(len);
+            for &(a, b) in edges.iter() {
+                sets.union(a, b);
+            }
+            let component = flood_fill(len, &edges);
+            let mut starts = component.clone();
+            starts.sort_unstable();
+            starts.dedup();
+            prop_assert_eq!(starts.len(), sets.sets());
+            for a in 0..len {
+                for b in 0..len {
+                    prop_assert_eq!(component[a] == component[b], sets.connected(a, b));
+                }
+            }
+        }
+    }
+}